@@ -1,11 +1,17 @@
+use std::path::PathBuf;
+
 use structopt::clap::AppSettings::{ColorAuto, ColoredHelp, DisableVersion};
+use structopt::clap::ArgGroup;
 use structopt::StructOpt;
 
+use crate::publish::MakeLatest;
+
 /// Options for running git-release.
 #[derive(StructOpt, Debug)]
 #[structopt(name = "git-release", about = "Make a github release for tags")]
 #[structopt(no_version, global_settings = &[DisableVersion])]
 #[structopt(setting(ColorAuto), setting(ColoredHelp))]
+#[structopt(group = ArgGroup::with_name("update").multiple(true))]
 pub struct Opt {
     /// Tag, tags, range of tags. Defaults to latest tag.
     ///
@@ -31,16 +37,110 @@ pub struct Opt {
     #[structopt(short, long, default_value = "origin")]
     pub remote: String,
 
-    /// Publish the release results to github.
+    /// Publish the release results to github, gitlab or gitea, based on the remote.
     #[structopt(short, long)]
     pub publish: bool,
 
+    /// The token for publishing to github, and for getting the pull requests.
+    #[structopt(long, env, hide_env_values = true)]
+    pub github_token: Option<String>,
+
+    /// The host of the GitHub Enterprise Server, e.g. github.example.com. Defaults to the host of
+    /// the remote.
+    #[structopt(long, env)]
+    pub github_host: Option<String>,
+
+    /// The token for publishing to gitlab.
+    #[structopt(long, env, hide_env_values = true)]
+    pub gitlab_token: Option<String>,
+
+    /// The url of the self-hosted gitlab instance, e.g. https://gitlab.example.com.
+    #[structopt(long, env)]
+    pub gitlab_url: Option<String>,
+
+    /// The token for publishing to gitea or forgejo.
     #[structopt(long, env, hide_env_values = true)]
-    pub github_token: String,
+    pub gitea_token: Option<String>,
+
+    /// The url of the gitea or forgejo instance, if its host doesn't contain their names.
+    #[structopt(long, env)]
+    pub gitea_url: Option<String>,
 
     /// If publishing fails, try fording it. This will replace the previous contents!
-    #[structopt(short, long)]
+    #[structopt(short, long, group = "update")]
     pub force: bool,
+
+    /// Update the existing release, or create it if there is none. Only the notes between the
+    /// git-release markers are replaced, so the hand-written parts of the release are kept.
+    #[structopt(long, group = "update")]
+    pub upsert: bool,
+
+    /// Show the changes to the existing release before it is updated with --force or --upsert,
+    /// and ask for a confirmation. Without a terminal, it fails if there are any changes, unless
+    /// --yes is given.
+    #[structopt(long, requires = "update")]
+    pub diff: bool,
+
+    /// Apply the changes that are shown with --diff without a confirmation.
+    #[structopt(long, requires = "diff")]
+    pub yes: bool,
+
+    /// Create the release as a draft. Use the publish-draft command to publish it later.
+    #[structopt(long)]
+    pub draft: bool,
+
+    /// Mark the release as a prerelease.
+    #[structopt(long)]
+    pub prerelease: bool,
+
+    /// Decide if the release is marked as the latest release on github.
+    #[structopt(long, possible_values = &["true", "false", "legacy"])]
+    pub make_latest: Option<MakeLatest>,
+
+    /// Upload the files matching the glob pattern to the github release, e.g. 'dist/*.tar.gz'.
+    /// It can be given more than once.
+    #[structopt(long = "asset", number_of_values = 1)]
+    pub assets: Vec<String>,
+
+    /// Upload a SHA256SUMS file with the checksums of the assets.
+    #[structopt(long)]
+    pub checksums: bool,
+
+    /// The number of times a request to github is retried when it hits the rate limits or fails
+    /// temporarily.
+    #[structopt(long, default_value = "3")]
+    pub retries: u32,
+
+    /// The longest time in seconds to wait before retrying a request to github. Requests are not
+    /// retried if the server asks for a longer wait.
+    #[structopt(long, default_value = "60")]
+    pub max_retry_delay: u64,
+
+    /// The user agent of the requests to github.
+    #[structopt(long)]
+    pub user_agent: Option<String>,
+
+    /// The url of the proxy for the requests to github. Otherwise the HTTPS_PROXY and HTTP_PROXY
+    /// environment variables are used.
+    #[structopt(long)]
+    pub proxy: Option<String>,
+
+    /// Print the requests to github and their responses.
+    #[structopt(short, long)]
+    pub verbose: bool,
+
+    /// Use the title and labels of the pull request of each commit instead of its message.
+    #[structopt(long)]
+    pub pull_requests: bool,
+
+    /// The config file of the project. Defaults to .git-release.yml if it exists.
+    #[structopt(short, long, parse(from_os_str))]
+    pub config: Option<PathBuf>,
+
+    /// Leave out the commits that are already released in the given reference, e.g. the tag of
+    /// the next major version. It can be given more than once.
+    #[structopt(long, number_of_values = 1)]
+    pub released_in: Vec<String>,
 }
 
 #[derive(StructOpt, Debug)]
@@ -49,11 +149,18 @@ pub enum Command {
     Version,
     /// Publish the release information to github.
     Publish,
+    /// Publish the draft release of the tag, after its notes are reviewed.
+    PublishDraft,
 }
 
 impl Opt {
     pub fn new() -> Opt {
-        let mut opt = Opt::from_args();
+        Opt::from_args().with_tags()
+    }
+
+    /// Sets the tags from the tag argument.
+    pub fn with_tags(self) -> Opt {
+        let mut opt = self;
         if let Some(ref tag) = opt.tag {
             if !tag.contains("..") {
                 opt.tags = Tag::Single(tag.clone());
@@ -71,16 +178,11 @@ impl Opt {
 }
 
 /// Tag is the value of the tag argument provided by the user.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Tag {
+    #[default]
     None,
     Single(String),
     From(String),
     Range(String, String),
 }
-
-impl Default for Tag {
-    fn default() -> Self {
-        Tag::None
-    }
-}
@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::workspace::errors::GRError;
+
+#[cfg(test)]
+#[path = "./asset_test.rs"]
+mod asset_test;
+
+/// The name of the file that lists the checksums of the assets.
+pub const CHECKSUMS_NAME: &str = "SHA256SUMS";
+
+/// An Asset is a file that is uploaded to the release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Asset {
+    /// The file name of the asset.
+    pub name: String,
+    pub content_type: String,
+    pub contents: Vec<u8>,
+}
+
+impl Asset {
+    /// Reads the file at the path. The content type is guessed from its extension.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read, an `Err` is returned.
+    pub fn read(path: &Path) -> Result<Self, GRError> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .ok_or_else(|| GRError::AssetRead(path.display().to_string(), None))?;
+        let contents = std::fs::read(path)
+            .map_err(|err| GRError::AssetRead(path.display().to_string(), Some(err)))?;
+        let content_type = mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .to_string();
+        Ok(Asset {
+            name,
+            content_type,
+            contents,
+        })
+    }
+
+    /// Returns the files matching the glob patterns, in the order of the patterns. If `checksums`
+    /// is set, a `SHA256SUMS` file is added with the checksums of all the files, in the format of
+    /// the `sha256sum` command.
+    ///
+    /// # Errors
+    ///
+    /// If a pattern is not valid or doesn't match any files, or a file can't be read, an `Err` is
+    /// returned.
+    pub fn load(patterns: &[String], checksums: bool) -> Result<Vec<Self>, GRError> {
+        let mut assets: Vec<Asset> = vec![];
+        let mut paths = vec![];
+        for pattern in patterns {
+            let mut matched = false;
+            for path in glob::glob(pattern)? {
+                let path = path.map_err(|err| {
+                    GRError::AssetRead(err.path().display().to_string(), Some(err.into()))
+                })?;
+                if !path.is_file() {
+                    continue;
+                }
+                matched = true;
+                if !paths.contains(&path) {
+                    assets.push(Asset::read(&path)?);
+                    paths.push(path);
+                }
+            }
+            if !matched {
+                return Err(GRError::AssetNotFound(pattern.clone()));
+            }
+        }
+        if checksums && !assets.is_empty() {
+            let sums = assets
+                .iter()
+                .map(|asset| format!("{:x}  {}\n", Sha256::digest(&asset.contents), asset.name))
+                .collect::<String>();
+            assets.push(Asset {
+                name: CHECKSUMS_NAME.to_owned(),
+                content_type: "text/plain".to_owned(),
+                contents: sums.into_bytes(),
+            });
+        }
+        Ok(assets)
+    }
+}
@@ -0,0 +1,56 @@
+use super::*;
+
+#[cfg(test)]
+mod load {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn globs() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(dir.path().join("app.tar.gz"), "tarball")?;
+        std::fs::write(dir.path().join("app.zip"), "zip")?;
+        std::fs::write(dir.path().join("notes.txt"), "notes")?;
+        std::fs::create_dir(dir.path().join("sub.zip"))?;
+
+        let patterns = vec![
+            dir.path().join("*.zip").display().to_string(),
+            dir.path().join("app.*").display().to_string(),
+        ];
+        let assets = Asset::load(&patterns, false)?;
+        let names: Vec<_> = assets.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(vec!["app.zip", "app.tar.gz"], names);
+        assert_eq!("application/zip", assets[0].content_type);
+        assert_eq!("application/gzip", assets[1].content_type);
+        assert_eq!(b"zip".to_vec(), assets[0].contents);
+        Ok(())
+    }
+
+    #[test]
+    fn checksums() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        std::fs::write(dir.path().join("a.bin"), "")?;
+        std::fs::write(dir.path().join("b.bin"), "abc")?;
+
+        let patterns = vec![dir.path().join("*.bin").display().to_string()];
+        let assets = Asset::load(&patterns, true)?;
+        assert_eq!(3, assets.len());
+        let sums = &assets[2];
+        assert_eq!(CHECKSUMS_NAME, sums.name);
+        assert_eq!("text/plain", sums.content_type);
+        let want = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  a.bin\n\
+                    ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad  b.bin\n";
+        assert_eq!(want, String::from_utf8(sums.contents.clone())?);
+        Ok(())
+    }
+
+    #[test]
+    fn no_match() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        let patterns = vec![dir.path().join("*.zip").display().to_string()];
+        assert!(Asset::load(&patterns, true).is_err());
+        assert!(Asset::load(&["[".to_owned()], false).is_err());
+        assert!(Asset::load(&[], true)?.is_empty());
+        Ok(())
+    }
+}
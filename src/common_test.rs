@@ -1,5 +1,5 @@
 use git2::{Oid, Repository, RepositoryInitOptions};
-use std::fs::File;
+use std::fs::{self, File};
 use std::path::Path;
 use tempfile::TempDir;
 
@@ -45,15 +45,46 @@ pub fn commit(repo: &Repository, filename: &str, msg: Option<&str>) -> (Oid, Oid
     let sig = t!(repo.signature());
     let head_id = t!(repo.refname_to_id("HEAD"));
     let parent = t!(repo.find_commit(head_id));
-    let msg = if let Some(msg) = msg {
-        msg
-    } else {
-        "some commit"
-    };
+    let msg = msg.unwrap_or("some commit");
     let commit = t!(repo.commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&parent]));
     (commit, tree_id)
 }
 
+pub fn commit_file(repo: &Repository, filename: &str, contents: &str, msg: &str) -> Oid {
+    let mut index = t!(repo.index());
+    let path = repo.path().parent().unwrap().join(filename);
+    t!(fs::create_dir_all(path.parent().unwrap()));
+    t!(fs::write(path, contents));
+    t!(index.add_path(Path::new(filename)));
+    t!(index.write());
+
+    let tree_id = t!(index.write_tree());
+    let tree = t!(repo.find_tree(tree_id));
+    let sig = t!(repo.signature());
+    let head_id = t!(repo.refname_to_id("HEAD"));
+    let parent = t!(repo.find_commit(head_id));
+    t!(repo.commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&parent]))
+}
+
+/// Creates a commit on top of the `parent` without moving the HEAD, as if it was made on another
+/// branch.
+pub fn branch_commit(repo: &Repository, parent: Oid, msg: &str) -> Oid {
+    let parent = t!(repo.find_commit(parent));
+    let tree = t!(parent.tree());
+    let sig = t!(repo.signature());
+    t!(repo.commit(None, &sig, &sig, msg, &tree, &[&parent]))
+}
+
+/// Merges the `other` commit into the HEAD.
+pub fn merge(repo: &Repository, other: Oid, msg: &str) -> Oid {
+    let head_id = t!(repo.refname_to_id("HEAD"));
+    let head = t!(repo.find_commit(head_id));
+    let other = t!(repo.find_commit(other));
+    let tree = t!(head.tree());
+    let sig = t!(repo.signature());
+    t!(repo.commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&head, &other]))
+}
+
 pub fn commit_tag(repo: &Repository, filename: &str, tag: &str) -> (Oid, Oid) {
     let (commit, _) = commit(repo, filename, None);
     let obj = repo.find_object(commit, None).unwrap();
@@ -78,3 +109,20 @@ pub fn new_commit<'a>(
     let (oid, _) = commit(&repo, filename, Some(body));
     Ok((repo, oid))
 }
+
+pub fn tag(repo: &Repository, oid: Oid, tag: &str) -> Oid {
+    let obj = repo.find_object(oid, None).unwrap();
+    let sig = repo.signature().unwrap();
+    repo.tag(tag, &obj, &sig, "msg", false).unwrap()
+}
+
+/// Applies the changes of the commit on the HEAD with the given message.
+pub fn cherry_pick(repo: &Repository, oid: Oid, msg: &str) -> Oid {
+    let commit = t!(repo.find_commit(oid));
+    let head_id = t!(repo.refname_to_id("HEAD"));
+    let head = t!(repo.find_commit(head_id));
+    let mut index = t!(repo.cherrypick_commit(&commit, &head, 0, None));
+    let tree = t!(repo.find_tree(t!(index.write_tree_to(repo))));
+    let sig = t!(repo.signature());
+    t!(repo.commit(Some("HEAD"), &sig, &sig, msg, &tree, &[&head]))
+}
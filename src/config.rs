@@ -0,0 +1,457 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::workspace::commit::Verb;
+use crate::workspace::errors::GRError;
+use crate::workspace::remote::RemoteInfo;
+use crate::workspace::tag::DEFAULT_NAME_TEMPLATE;
+
+#[cfg(test)]
+#[path = "./config_test.rs"]
+mod config_test;
+
+/// The file that is read from the root of the repository when no config file is given.
+pub const DEFAULT_PATH: &str = ".git-release.yml";
+
+/// The paths of github's release notes configuration in the repository.
+pub const GITHUB_RELEASE_PATHS: [&str; 2] = [".github/release.yml", ".github/release.yaml"];
+
+/// Config holds the per-project settings. All settings are optional and the zero value keeps the
+/// default behaviour.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Issue trackers, other than github, that are referenced in the commit messages.
+    pub trackers: Vec<Tracker>,
+
+    /// Maps the labels of pull requests to the section they should be listed in. This is only
+    /// used when the pull requests are fetched.
+    #[serde(deserialize_with = "labels")]
+    pub labels: HashMap<String, Verb>,
+
+    /// Decides how the commits are grouped into sections. Use the `group_by()` method to get the
+    /// effective value.
+    pub group_by: Option<GroupBy>,
+
+    /// Subdivides each section by another grouping, e.g. by scope within each verb.
+    pub subgroup_by: Option<GroupBy>,
+
+    /// Normalises the scopes of the commits, and optionally checks them against an allowed list.
+    #[serde(deserialize_with = "scopes")]
+    pub scopes: Scopes,
+
+    /// Commits matching any of these rules are left out of the release.
+    #[serde(deserialize_with = "ignore_rules")]
+    pub ignore: Vec<IgnoreRule>,
+
+    /// Decides how the merge commits are handled.
+    pub merges: Merges,
+
+    /// Lists each `* feat: something` line in the body of the squash merged commits separately.
+    pub expand_squashed: bool,
+
+    /// Notes the entries that were cherry-picked into the same release.
+    pub note_backports: bool,
+
+    /// Links the entries to their commits and issues, and adds a link to the changes between the
+    /// tags. The links are made for the host of the remote.
+    pub links: bool,
+
+    /// The template of the name of the releases, e.g. `{project} {version} "{codename}"`. See
+    /// `TagInfo::release_name()` for the placeholders. Defaults to `Release {tag}`.
+    pub name: Option<String>,
+
+    /// The remote that the links are made for. It is set from the repository.
+    #[serde(skip)]
+    pub remote: Option<RemoteInfo>,
+
+    /// The categories of the release when grouping by labels. It has the same format as the
+    /// `changelog` section of github's `.github/release.yml` file.
+    pub changelog: Changelog,
+
+    /// Uses the changelog of github's `.github/release.yml` file in the repository, if the
+    /// project doesn't have its own. See `merge_github_release()`.
+    pub github_release: bool,
+}
+
+/// GroupBy is the way the commits are grouped into the sections of the release.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// By the verb in the commit summary, e.g. `feat` or `fix`.
+    #[default]
+    Verb,
+    /// By the labels of the pull requests, as configured in the `changelog` section.
+    Label,
+    /// By the scope in the commit summary, e.g. `repo` in `feat(repo): ...`.
+    Scope,
+}
+
+/// Scopes holds the rules for normalising the scopes of the commits. When `case_insensitive` is
+/// set, the scopes and the targets of the aliases are lowercased.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scopes {
+    pub case_insensitive: bool,
+    pub aliases: HashMap<String, String>,
+    /// If not empty, the scopes that are not in this list are reported.
+    pub allowed: Vec<String>,
+    /// Decides what happens when a commit has a scope that is not allowed.
+    pub unknown: UnknownScope,
+}
+
+impl Scopes {
+    /// Returns the normalised form of the scope.
+    pub fn normalise(&self, scope: &str) -> String {
+        let scope = match self.case_insensitive {
+            true => scope.to_lowercase(),
+            false => scope.to_owned(),
+        };
+        let alias = self
+            .aliases
+            .iter()
+            .find(|(from, _)| match self.case_insensitive {
+                true => from.to_lowercase() == scope,
+                false => **from == scope,
+            });
+        match (alias, self.case_insensitive) {
+            (Some((_, to)), true) => to.to_lowercase(),
+            (Some((_, to)), false) => to.clone(),
+            (None, _) => scope,
+        }
+    }
+
+    /// Returns an error if any of the scopes are empty, or two of the allowed scopes are the same
+    /// after normalisation, as they would be rendered as empty or duplicated sections. When
+    /// `case_insensitive` is set, the aliases that only differ in case should have the same
+    /// target, otherwise the scope would be normalised to either of them.
+    fn validate(&self) -> Result<(), String> {
+        let names = self.aliases.iter().flat_map(|(from, to)| [from, to]);
+        if names
+            .chain(&self.allowed)
+            .any(|name| name.trim().is_empty())
+        {
+            return Err("scope names can't be empty".to_owned());
+        }
+        if self.case_insensitive {
+            let mut aliases: Vec<(&String, &String)> = self.aliases.iter().collect();
+            aliases.sort();
+            for (i, (from, to)) in aliases.iter().enumerate() {
+                let collision = aliases[i + 1..].iter().find(|(other, other_to)| {
+                    other.to_lowercase() == from.to_lowercase()
+                        && other_to.to_lowercase() != to.to_lowercase()
+                });
+                if let Some((other, _)) = collision {
+                    return Err(format!(
+                        "the aliases '{from}' and '{other}' point at different scopes"
+                    ));
+                }
+            }
+        }
+        let mut seen: Vec<(String, &str)> = vec![];
+        for scope in &self.allowed {
+            let normalised = self.normalise(scope);
+            if let Some((_, other)) = seen.iter().find(|(n, _)| *n == normalised) {
+                return Err(format!(
+                    "the allowed scopes '{other}' and '{scope}' are both '{normalised}'"
+                ));
+            }
+            seen.push((normalised, scope));
+        }
+        Ok(())
+    }
+
+    /// Returns true if there is no allowed list, or the normalised scope is in it.
+    pub fn is_allowed(&self, scope: &str) -> bool {
+        self.allowed.is_empty() || self.allowed.iter().any(|a| self.normalise(a) == scope)
+    }
+}
+
+/// UnknownScope is the action that is taken when a commit has a scope that is not allowed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownScope {
+    /// Prints a warning and continues.
+    #[default]
+    Warn,
+    /// Stops with an error.
+    Reject,
+}
+
+/// Merges is the policy for the merge commits between the tags.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Merges {
+    /// All commits are included, including the merge commits.
+    #[default]
+    Include,
+    /// The merge commits are left out.
+    Skip,
+    /// Only the commits on the first parent history are included.
+    FirstParent,
+    /// Only the commits on the first parent history are included, and each merge commit is listed
+    /// as the branch it merged: with the title of the github pull request or the gitlab merge
+    /// request, or with the commits of the branch for the plain merges.
+    PullRequests,
+}
+
+/// An IgnoreRule matches the commits that should be left out of the release. All the given fields
+/// should match for the rule to apply. The `author`, `email` and `summary` fields are regular
+/// expressions, and the `trailer` is either a key (e.g. `Changelog`), or a key and a value (e.g.
+/// `Changelog: skip`). The `verb` should be one of the known verbs, e.g. `chore` or `misc`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IgnoreRule {
+    #[serde(deserialize_with = "known_verb")]
+    pub verb: Option<Verb>,
+    pub scope: Option<String>,
+    #[serde(deserialize_with = "optional_regex")]
+    pub author: Option<Regex>,
+    #[serde(deserialize_with = "optional_regex")]
+    pub email: Option<Regex>,
+    #[serde(deserialize_with = "optional_regex")]
+    pub summary: Option<Regex>,
+    pub trailer: Option<String>,
+}
+
+impl IgnoreRule {
+    /// Returns true if none of the fields are set. Such a rule would match all commits.
+    pub fn is_empty(&self) -> bool {
+        self.verb.is_none()
+            && self.scope.is_none()
+            && self.author.is_none()
+            && self.email.is_none()
+            && self.summary.is_none()
+            && self.trailer.is_none()
+    }
+
+    /// Returns true if the trailer of the rule matches the given key and value. Keys and values
+    /// are compared case-insensitively.
+    pub fn matches_trailer(&self, key: &str, value: &str) -> bool {
+        let Some(ref trailer) = self.trailer else {
+            return false;
+        };
+        match trailer.split_once(':') {
+            Some((k, v)) => {
+                k.trim().eq_ignore_ascii_case(key) && v.trim().eq_ignore_ascii_case(value.trim())
+            },
+            None => trailer.trim().eq_ignore_ascii_case(key),
+        }
+    }
+}
+
+/// GithubRelease is the contents of github's `.github/release.yml` file. Only the sections that
+/// are relevant to this application are read.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct GithubRelease {
+    pub changelog: Changelog,
+}
+
+impl FromStr for GithubRelease {
+    type Err = GRError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(GithubRelease::default());
+        }
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
+
+/// Changelog lists the categories of a release based on the labels of the pull requests.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Changelog {
+    pub exclude: Exclude,
+    pub categories: Vec<Category>,
+}
+
+impl Changelog {
+    /// Returns true if there are no categories or exclusions.
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty() && self.exclude.is_empty()
+    }
+}
+
+/// A Category is a section of the release that contains the pull requests with any of its
+/// labels. The `*` label matches all pull requests.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Category {
+    pub title: String,
+    pub labels: Vec<String>,
+    pub exclude: Exclude,
+}
+
+impl Category {
+    /// Returns true if any of the given labels belongs to this category, and neither the labels
+    /// nor the author are excluded from it.
+    pub fn matches(&self, labels: &[String], author: Option<&str>) -> bool {
+        let matched = self
+            .labels
+            .iter()
+            .any(|label| label == "*" || labels.contains(label));
+        matched && !self.exclude.matches(labels, author)
+    }
+}
+
+/// Exclude lists the labels and the authors that remove a pull request from the release or a
+/// category.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Exclude {
+    pub labels: Vec<String>,
+    pub authors: Vec<String>,
+}
+
+impl Exclude {
+    /// Returns true if any of the given labels, or the author is excluded.
+    pub fn matches(&self, labels: &[String], author: Option<&str>) -> bool {
+        labels.iter().any(|label| self.labels.contains(label))
+            || author.is_some_and(|author| self.authors.iter().any(|a| a == author))
+    }
+
+    /// Returns true if nothing is excluded.
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.authors.is_empty()
+    }
+}
+
+impl Config {
+    /// Reads the config from the given file. If the file doesn't exist and `required` is false,
+    /// the default config is returned.
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be read or its contents are not valid, an `Err` is returned.
+    pub fn load<T: AsRef<Path>>(path: T, required: bool) -> Result<Self, GRError> {
+        let path = path.as_ref();
+        if !required && !path.exists() {
+            return Ok(Config::default());
+        }
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| GRError::ConfigRead(path.display().to_string(), err))?;
+        contents.parse()
+    }
+}
+
+impl Config {
+    /// Returns how the commits should be grouped. Defaults to grouping by verbs.
+    pub fn group_by(&self) -> GroupBy {
+        self.group_by.unwrap_or_default()
+    }
+
+    /// Returns how each section should be subdivided, if it differs from the main grouping.
+    pub fn subgroup_by(&self) -> Option<GroupBy> {
+        self.subgroup_by.filter(|&by| by != self.group_by())
+    }
+
+    /// Returns the template of the name of the releases.
+    pub fn name_template(&self) -> &str {
+        self.name.as_deref().unwrap_or(DEFAULT_NAME_TEMPLATE)
+    }
+
+    /// Returns the remote to make the links for, if the links are enabled.
+    pub fn links(&self) -> Option<&RemoteInfo> {
+        self.remote.as_ref().filter(|_| self.links)
+    }
+
+    /// Uses the changelog of github's release configuration if the project doesn't have its own.
+    /// In that case, unless the grouping is set explicitly, the commits are grouped by labels.
+    pub fn merge_github_release(&mut self, release: GithubRelease) {
+        if !self.changelog.is_empty() || release.changelog.is_empty() {
+            return;
+        }
+        self.changelog = release.changelog;
+        self.group_by.get_or_insert(GroupBy::Label);
+    }
+}
+
+impl FromStr for Config {
+    type Err = GRError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Ok(Config::default());
+        }
+        Ok(serde_yaml::from_str(s)?)
+    }
+}
+
+/// A Tracker is an issue tracker that has keys like `PROJ-1234`. The `pattern` is matched against
+/// the commit message, and each match is linked with the `url` template, where `{key}` is
+/// replaced with the match.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tracker {
+    #[serde(deserialize_with = "regex")]
+    pub pattern: Regex,
+    pub url: String,
+}
+
+impl Tracker {
+    /// Returns the url of the given key.
+    pub fn link(&self, key: &str) -> String {
+        self.url.replace("{key}", key)
+    }
+}
+
+fn regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+fn optional_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Regex>, D::Error> {
+    regex(deserializer).map(Some)
+}
+
+/// Returns the verb of the name, or an error if it is not known, as it would otherwise be read as
+/// `Verb::Misc`.
+fn parse_verb(name: String) -> Result<Verb, String> {
+    match Verb::from(name.clone()) {
+        Verb::Misc if !name.eq_ignore_ascii_case("misc") => Err(format!("unknown verb: {name}")),
+        verb => Ok(verb),
+    }
+}
+
+/// Rejects the verbs that are not known, see `parse_verb()`.
+fn known_verb<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Verb>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    parse_verb(name).map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Rejects the labels that are mapped to unknown verbs, see `parse_verb()`.
+fn labels<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Verb>, D::Error> {
+    HashMap::<String, String>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(label, name)| {
+            parse_verb(name)
+                .map(|verb| (label.clone(), verb))
+                .map_err(|err| serde::de::Error::custom(format!("label '{label}': {err}")))
+        })
+        .collect()
+}
+
+/// Rejects the scopes that are not valid, see `Scopes::validate()`.
+fn scopes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Scopes, D::Error> {
+    let scopes = Scopes::deserialize(deserializer)?;
+    scopes.validate().map_err(serde::de::Error::custom)?;
+    Ok(scopes)
+}
+
+/// Rejects the ignore rules that don't have any fields, which would leave out all commits.
+fn ignore_rules<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<IgnoreRule>, D::Error> {
+    let rules = Vec::<IgnoreRule>::deserialize(deserializer)?;
+    match rules.iter().position(IgnoreRule::is_empty) {
+        Some(i) => Err(serde::de::Error::custom(format!(
+            "ignore rule {} has no fields, it would match all commits",
+            i + 1
+        ))),
+        None => Ok(rules),
+    }
+}
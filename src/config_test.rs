@@ -0,0 +1,381 @@
+use std::io::Write;
+
+use super::Config;
+
+#[cfg(test)]
+mod load {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn missing_file_not_required() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        let config = Config::load(dir.path().join("not_exists.yml"), false)?;
+        assert!(config.trackers.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_file_required() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::TempDir::new()?;
+        let config = Config::load(dir.path().join("not_exists.yml"), true);
+        assert!(config.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn from_file() -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = tempfile::NamedTempFile::new()?;
+        writeln!(
+            file,
+            "trackers:\n  - pattern: 'PROJ-\\d+'\n    url: https://example.com/browse/{{key}}"
+        )?;
+        let config = Config::load(file.path(), true)?;
+        assert_eq!(1, config.trackers.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod trackers {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn empty_config() -> Result<(), Box<dyn std::error::Error>> {
+        for contents in ["", "\n", "trackers: []"] {
+            let config: Config = contents.parse()?;
+            assert!(config.trackers.is_empty(), "{contents}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn link() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = r#"
+trackers:
+  - pattern: 'PROJ-\d+'
+    url: https://jira.example.com/browse/{key}
+  - pattern: 'ENG-\d+'
+    url: https://linear.app/team/issue/{key}
+"#
+        .parse()?;
+        let tracker = &config.trackers[0];
+        assert!(tracker.pattern.is_match("PROJ-123"));
+        assert_eq!(
+            "https://jira.example.com/browse/PROJ-123",
+            tracker.link("PROJ-123")
+        );
+        let tracker = &config.trackers[1];
+        assert_eq!("https://linear.app/team/issue/ENG-1", tracker.link("ENG-1"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let config = "trackers:\n  - pattern: 'PROJ-(\\d+'\n    url: https://example.com/{key}"
+            .parse::<Config>();
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn unknown_field() {
+        let config =
+            "trackers:\n  - pattern: 'PROJ'\n    link: https://example.com/{key}".parse::<Config>();
+        assert!(config.is_err());
+    }
+}
+
+#[cfg(test)]
+mod labels {
+    use super::*;
+    use crate::workspace::commit::Verb;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn verbs() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config =
+            "labels:\n  bug: fix\n  enhancement: Feature\n  other: misc".parse()?;
+        assert_eq!(Some(&Verb::Fix), config.labels.get("bug"));
+        assert_eq!(Some(&Verb::Feature), config.labels.get("enhancement"));
+        assert_eq!(Some(&Verb::Misc), config.labels.get("other"));
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_verb() {
+        let res = "labels:\n  bug: fixx".parse::<Config>();
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("label 'bug': unknown verb: fixx"), "{err}");
+    }
+}
+
+#[cfg(test)]
+mod changelog {
+    use super::*;
+    use crate::config::GroupBy;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn defaults() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = "".parse()?;
+        assert_eq!(GroupBy::Verb, config.group_by());
+        assert!(config.changelog.categories.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn categories() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = r#"
+group_by: label
+changelog:
+  exclude:
+    labels: [skip-changelog]
+  categories:
+    - title: Features
+      labels: [enhancement]
+      exclude:
+        labels: [internal]
+    - title: Other
+      labels: ['*']
+"#
+        .parse()?;
+        assert_eq!(GroupBy::Label, config.group_by());
+        let labels = |l: &[&str]| l.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+
+        let changelog = &config.changelog;
+        assert!(changelog
+            .exclude
+            .matches(&labels(&["bug", "skip-changelog"]), None));
+        assert!(!changelog.exclude.matches(&labels(&["bug"]), None));
+
+        let features = &changelog.categories[0];
+        assert_eq!("Features", features.title);
+        assert!(features.matches(&labels(&["enhancement"]), None));
+        assert!(!features.matches(&labels(&["enhancement", "internal"]), None));
+        assert!(!features.matches(&labels(&["bug"]), None));
+
+        let other = &changelog.categories[1];
+        assert!(other.matches(&labels(&["bug"]), None));
+        assert!(other.matches(&[], None));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod github_release {
+    use super::*;
+    use crate::config::{GithubRelease, GroupBy};
+    use pretty_assertions::assert_eq;
+
+    const RELEASE_YML: &str = r#"
+changelog:
+  exclude:
+    labels: [ignore-for-release]
+    authors: [octocat]
+  categories:
+    - title: Breaking Changes 🛠
+      labels: [Semver-Major, breaking-change]
+    - title: Exciting New Features 🎉
+      labels: [Semver-Minor, enhancement]
+    - title: Other Changes
+      labels: ["*"]
+"#;
+
+    #[test]
+    fn uses_github_changelog() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config: Config = "".parse()?;
+        let release: GithubRelease = RELEASE_YML.parse()?;
+        config.merge_github_release(release);
+
+        assert_eq!(GroupBy::Label, config.group_by());
+        assert_eq!(3, config.changelog.categories.len());
+        let exclude = &config.changelog.exclude;
+        assert!(exclude.matches(&[], Some("octocat")));
+        assert!(!exclude.matches(&[], Some("arsham")));
+        assert!(!exclude.matches(&[], None));
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_grouping() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config: Config = "group_by: verb".parse()?;
+        config.merge_github_release(RELEASE_YML.parse()?);
+        assert_eq!(GroupBy::Verb, config.group_by());
+        assert_eq!(3, config.changelog.categories.len());
+        Ok(())
+    }
+
+    #[test]
+    fn project_changelog_wins() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config: Config =
+            "changelog:\n  categories:\n    - title: All\n      labels: ['*']".parse()?;
+        config.merge_github_release(RELEASE_YML.parse()?);
+        assert_eq!(GroupBy::Verb, config.group_by());
+        assert_eq!(1, config.changelog.categories.len());
+        assert_eq!("All", config.changelog.categories[0].title);
+        Ok(())
+    }
+
+    #[test]
+    fn opt_in() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = "".parse()?;
+        assert!(!config.github_release);
+        let config: Config = "github_release: true".parse()?;
+        assert!(config.github_release);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_github_release() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config: Config = "".parse()?;
+        config.merge_github_release("".parse()?);
+        assert_eq!(GroupBy::Verb, config.group_by());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod subgroup_by {
+    use super::*;
+    use crate::config::GroupBy;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn grouping() -> Result<(), Box<dyn std::error::Error>> {
+        let tcs = vec![
+            ("", None),
+            ("subgroup_by: scope", Some(GroupBy::Scope)),
+            ("subgroup_by: verb", None),
+            ("group_by: scope\nsubgroup_by: verb", Some(GroupBy::Verb)),
+            ("group_by: scope\nsubgroup_by: scope", None),
+            ("group_by: label\nsubgroup_by: scope", Some(GroupBy::Scope)),
+        ];
+        for (contents, want) in tcs {
+            let config: Config = contents.parse()?;
+            assert_eq!(want, config.subgroup_by(), "{contents}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod scopes {
+    use super::*;
+    use crate::config::UnknownScope;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn defaults() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = "".parse()?;
+        assert_eq!("Repo", config.scopes.normalise("Repo"));
+        assert!(config.scopes.is_allowed("anything"));
+        assert_eq!(UnknownScope::Warn, config.scopes.unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn allowed() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config =
+            "scopes:\n  case_insensitive: true\n  allowed: [Repo, server]\n  unknown: reject"
+                .parse()?;
+        let scopes = &config.scopes;
+        assert!(scopes.is_allowed("repo"));
+        assert!(scopes.is_allowed("server"));
+        assert!(!scopes.is_allowed("docs"));
+        assert_eq!(UnknownScope::Reject, scopes.unknown);
+        Ok(())
+    }
+
+    #[test]
+    fn aliases_differing_in_case() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config =
+            "scopes:\n  case_insensitive: true\n  aliases:\n    Repo: repo\n    REPO: Repo"
+                .parse()?;
+        assert_eq!("repo", config.scopes.normalise("rEpO"));
+        let config: Config = "scopes:\n  aliases:\n    Repo: repo\n    repo: server".parse()?;
+        assert_eq!("repo", config.scopes.normalise("Repo"));
+        assert_eq!("server", config.scopes.normalise("repo"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid() {
+        let tcs = [
+            (
+                "scopes:\n  allowed: [repo, '']",
+                "scope names can't be empty",
+            ),
+            (
+                "scopes:\n  aliases:\n    repository: ' '",
+                "scope names can't be empty",
+            ),
+            (
+                "scopes:\n  allowed: [repo, server, repo]",
+                "the allowed scopes 'repo' and 'repo' are both 'repo'",
+            ),
+            (
+                "scopes:\n  aliases:\n    repository: repo\n  allowed: [repo, repository]",
+                "the allowed scopes 'repo' and 'repository' are both 'repo'",
+            ),
+            (
+                "scopes:\n  case_insensitive: true\n  allowed: [Repo, repo]",
+                "the allowed scopes 'Repo' and 'repo' are both 'repo'",
+            ),
+            (
+                "scopes:\n  case_insensitive: true\n  aliases:\n    Repo: repo\n    repo: server",
+                "the aliases 'Repo' and 'repo' point at different scopes",
+            ),
+        ];
+        for (config, want) in tcs {
+            let err = config.parse::<Config>().unwrap_err().to_string();
+            assert!(err.contains(want), "{config}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod name_template {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn default_and_custom() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = "".parse()?;
+        assert_eq!("Release {tag}", config.name_template());
+        let config: Config = "name: '{project} {version}'".parse()?;
+        assert_eq!("{project} {version}", config.name_template());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ignore {
+    use super::*;
+    use crate::workspace::commit::Verb;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn rules() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config =
+            "ignore:\n  - verb: Chore\n  - verb: misc\n  - trailer: skip".parse()?;
+        assert_eq!(3, config.ignore.len());
+        assert_eq!(Some(Verb::Chore), config.ignore[0].verb);
+        assert_eq!(Some(Verb::Misc), config.ignore[1].verb);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_rule() {
+        let res = "ignore:\n  - verb: chore\n  - {}".parse::<Config>();
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("ignore rule 2 has no fields"), "{err}");
+    }
+
+    #[test]
+    fn unknown_verb() {
+        let res = "ignore:\n  - verb: Fixx".parse::<Config>();
+        let err = res.unwrap_err().to_string();
+        assert!(err.contains("unknown verb: Fixx"), "{err}");
+    }
+}
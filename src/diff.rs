@@ -0,0 +1,93 @@
+use colored::*;
+use difference::{Changeset, Difference};
+
+#[cfg(test)]
+#[path = "./diff_test.rs"]
+mod diff_test;
+
+/// The number of unchanged lines that are shown around the changes.
+const CONTEXT: usize = 3;
+
+/// Returns the unified diff of the changes from `old` to `new`, with the `@@ -1,3 +1,4 @@`
+/// headers of the hunks. It is empty if there are no changes.
+pub fn unified(old: &str, new: &str) -> String {
+    let lines = lines(old, new);
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != ' ')
+        .map(|(i, _)| i)
+        .collect();
+    let Some(&first) = changed.first() else {
+        return String::new();
+    };
+
+    // Groups the changes that are close enough to share their context.
+    let mut hunks = vec![(first, first)];
+    for &i in &changed[1..] {
+        let last = hunks.last_mut().unwrap();
+        if i - last.1 <= 2 * CONTEXT {
+            last.1 = i;
+        } else {
+            hunks.push((i, i));
+        }
+    }
+
+    let mut res = String::new();
+    for (start, end) in hunks {
+        let start = start.saturating_sub(CONTEXT);
+        let end = (end + CONTEXT).min(lines.len() - 1);
+        let hunk = &lines[start..=end];
+        let old_start = lines[..start].iter().filter(|(op, _)| *op != '+').count();
+        let new_start = lines[..start].iter().filter(|(op, _)| *op != '-').count();
+        let old_len = hunk.iter().filter(|(op, _)| *op != '+').count();
+        let new_len = hunk.iter().filter(|(op, _)| *op != '-').count();
+        res.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(old_start, old_len),
+            range(new_start, new_len)
+        ));
+        for (op, line) in hunk {
+            res.push_str(&format!("{op}{line}\n"));
+        }
+    }
+    res
+}
+
+/// Colours the lines of the unified diff: removals in red, additions in green and the hunk
+/// headers in cyan.
+pub fn colorize(diff: &str) -> String {
+    diff.lines()
+        .map(|line| match line.chars().next() {
+            Some('-') => line.red().to_string(),
+            Some('+') => line.green().to_string(),
+            Some('@') => line.cyan().to_string(),
+            _ => line.to_owned(),
+        })
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Returns each line of the diff with its operation: `' '`, `'-'` or `'+'`.
+fn lines(old: &str, new: &str) -> Vec<(char, String)> {
+    let changeset = Changeset::new(old, new, "\n");
+    let mut res = vec![];
+    for diff in changeset.diffs {
+        let (op, chunk) = match diff {
+            Difference::Same(chunk) => (' ', chunk),
+            Difference::Rem(chunk) => ('-', chunk),
+            Difference::Add(chunk) => ('+', chunk),
+        };
+        res.extend(chunk.split('\n').map(|line| (op, line.to_owned())));
+    }
+    res
+}
+
+/// Returns the range of a hunk in the header. Empty ranges start at the line before them.
+fn range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    }
+}
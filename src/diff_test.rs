@@ -0,0 +1,48 @@
+use super::*;
+
+#[cfg(test)]
+mod unified {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn no_changes() {
+        assert_eq!("", unified("a\nb", "a\nb"));
+    }
+
+    #[test]
+    fn changed_line() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni";
+        let want = "@@ -2,7 +2,7 @@\n b\n c\n d\n-e\n+E\n f\n g\n h\n";
+        assert_eq!(want, unified(old, new));
+    }
+
+    #[test]
+    fn separate_hunks() {
+        let old = (1..=20).map(|i| i.to_string()).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new[0] = "one".to_owned();
+        new.push("21".to_owned());
+        let want = "@@ -1,4 +1,4 @@\n-1\n+one\n 2\n 3\n 4\n@@ -18,3 +18,4 @@\n 18\n 19\n 20\n+21\n";
+        assert_eq!(want, unified(&old.join("\n"), &new.join("\n")));
+    }
+
+    #[test]
+    fn empty_old() {
+        assert_eq!("@@ -0,0 +1,2 @@\n+a\n+b\n", unified("", "a\nb"));
+    }
+}
+
+#[cfg(test)]
+mod colorize {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn lines() {
+        colored::control::set_override(false);
+        let diff = "@@ -1 +1 @@\n-a\n+b\n c\n";
+        assert_eq!(diff, colorize(diff));
+    }
+}
@@ -1,51 +1,398 @@
-use octocrab::Octocrab;
+use std::collections::HashMap;
 
+use colored::*;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::Asset;
+use crate::publish::{self, DraftPublisher, Lifecycle, MakeLatest, PublishError, Publisher};
+use crate::retry::Retry;
+use crate::workspace::pull_request::PullRequest;
+
+#[cfg(test)]
+#[path = "./gh_test.rs"]
+mod gh_test;
+
+/// The url of the github API for the repositories on github.com.
+const DEFAULT_API_URL: &str = "https://api.github.com/";
+
+/// Returns the url of the API of the given github server, e.g. `https://github.example.com`.
+/// GitHub Enterprise Server serves its API under the `/api/v3` path.
+pub fn api_url(base_url: &str) -> String {
+    let base_url = base_url.trim_end_matches('/');
+    match base_url
+        .split_once("://")
+        .map_or(base_url, |(_, host)| host)
+    {
+        "github.com" | "api.github.com" => DEFAULT_API_URL.to_owned(),
+        _ => format!("{base_url}/api/v3/"),
+    }
+}
+
+/// The user agent of the requests, unless another one is given.
+pub const DEFAULT_USER_AGENT: &str = concat!("git-release/", env!("APP_VERSION"));
+
+/// ClientOptions are the settings of the github client.
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// Defaults to `DEFAULT_USER_AGENT`.
+    pub user_agent: Option<String>,
+    /// The url of the proxy for all requests. The proxies in the `HTTPS_PROXY` and `HTTP_PROXY`
+    /// environment variables are used otherwise.
+    pub proxy: Option<String>,
+    /// The budget for retrying the requests that hit the rate limits or fail temporarily.
+    pub retry: Retry,
+    /// Prints each request and its response status to the stderr.
+    pub log: bool,
+}
+
+/// Client is the authenticated client of the github API. It is made once, and all the requests to
+/// github go through it.
+pub struct Client {
+    http: reqwest::Client,
+    /// The url of the API, see `api_url()`.
+    api_url: reqwest::Url,
+    retry: Retry,
+    log: bool,
+}
+
+impl Client {
+    /// Returns a client for the API at the given url, e.g. a mock server in the tests.
+    ///
+    /// # Errors
+    ///
+    /// If the token, the url or the proxy are not valid, an `Err` is returned.
+    pub fn new(token: &str, api_url: &str, options: ClientOptions) -> Result<Self, PublishError> {
+        let invalid = |err: &dyn std::fmt::Display| PublishError::Client(err.to_string());
+        // The routes are relative to the url, so it should end with a slash.
+        let api_url = format!("{}/", api_url.trim_end_matches('/'));
+        let api_url = reqwest::Url::parse(&api_url).map_err(|err| invalid(&err))?;
+        let mut auth =
+            HeaderValue::from_str(&format!("Bearer {token}")).map_err(|err| invalid(&err))?;
+        auth.set_sensitive(true);
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, auth);
+        headers.insert(
+            ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+
+        let user_agent = options.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT);
+        let mut builder = reqwest::Client::builder()
+            .default_headers(headers)
+            .user_agent(user_agent);
+        if let Some(ref proxy) = options.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy).map_err(|err| invalid(&err))?);
+        }
+        Ok(Client {
+            http: builder.build().map_err(|err| invalid(&err))?,
+            api_url,
+            retry: options.retry,
+            log: options.log,
+        })
+    }
+
+    /// Returns a request to the route, which is relative to the url of the API, e.g.
+    /// `repos/arsham/shark/releases`. Absolute urls are used as they are.
+    fn request(
+        &self,
+        method: reqwest::Method,
+        route: &str,
+    ) -> Result<reqwest::RequestBuilder, PublishError> {
+        let url = self
+            .api_url
+            .join(route)
+            .map_err(|err| PublishError::Client(err.to_string()))?;
+        Ok(self.http.request(method, url))
+    }
+
+    /// Sends the request, and retries it based on the retry budget. The failed responses are
+    /// classified into the `PublishError` variants.
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, PublishError> {
+        self.retry
+            .send(request, async |request| {
+                let request = request.build()?;
+                let (method, url) = (request.method().clone(), request.url().clone());
+                let response = self.http.execute(request).await;
+                if self.log {
+                    let status = match response {
+                        Ok(ref response) => response.status().to_string(),
+                        Err(ref err) => err.to_string(),
+                    };
+                    eprintln!("{}", format!("{method} {url}: {status}").dimmed());
+                }
+                Ok(response?)
+            })
+            .await
+    }
+
+    /// Sends a request to the route with the json body, and returns its response.
+    async fn json<T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        route: &str,
+        body: Option<&impl Serialize>,
+    ) -> Result<T, PublishError> {
+        let mut request = self.request(method, route)?;
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        Ok(self.send(request).await?.json().await?)
+    }
+
+    /// Returns the response of the route.
+    async fn get<T: DeserializeOwned>(&self, route: &str) -> Result<T, PublishError> {
+        self.json(reqwest::Method::GET, route, None::<&()>).await
+    }
+}
+
+/// Release publishes the release of a tag to github.
 pub struct Release<'a> {
-    pub token: &'a str,
+    pub client: &'a Client,
     pub user: &'a str,
     pub repository: &'a str,
     pub tag: &'a str,
-    pub description: &'a str,
+    /// The name of the release, see `TagInfo::release_name()`.
+    pub name: &'a str,
+    pub lifecycle: Lifecycle,
+    /// The files that are uploaded to the release. The assets of the release with the same names
+    /// are replaced when the release is updated.
+    pub assets: &'a [Asset],
+}
+
+#[derive(Serialize)]
+struct ReleaseRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    draft: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prerelease: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    make_latest: Option<MakeLatest>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    id: u64,
+    tag_name: String,
+    draft: bool,
+    #[serde(default)]
+    body: Option<String>,
+    /// The url template for uploading the assets, e.g.
+    /// `https://uploads.github.com/repos/o/r/releases/1/assets{?name,label}`.
+    upload_url: String,
+    #[serde(default)]
+    assets: Vec<AssetResponse>,
+}
+
+#[derive(Deserialize)]
+struct AssetResponse {
+    id: u64,
+    name: String,
 }
 
 impl<'a> Release<'a> {
+    fn releases_route(&self) -> String {
+        format!("repos/{}/{}/releases", self.user, self.repository)
+    }
+
+    /// Returns the draft release of the tag. The tag endpoint of the API doesn't return the
+    /// drafts, so the pages of the releases are searched.
+    async fn find_draft(&self) -> Result<Option<ReleaseResponse>, PublishError> {
+        const PER_PAGE: usize = 100;
+        for page in 1.. {
+            let route = format!("{}?per_page={PER_PAGE}&page={page}", self.releases_route());
+            let releases: Vec<ReleaseResponse> = self.client.get(&route).await?;
+            let count = releases.len();
+            let draft = releases
+                .into_iter()
+                .find(|r| r.draft && r.tag_name == self.tag);
+            if draft.is_some() || count < PER_PAGE {
+                return Ok(draft);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the release of the tag, including the drafts.
+    async fn release(&self) -> Result<ReleaseResponse, PublishError> {
+        let route = format!(
+            "{}/tags/{}",
+            self.releases_route(),
+            publish::encode_segment(self.tag)
+        );
+        match self.client.get(&route).await {
+            Err(PublishError::NotFound(message)) => self
+                .find_draft()
+                .await?
+                .ok_or(PublishError::NotFound(message)),
+            res => res,
+        }
+    }
+
+    /// Uploads the assets to the release. The existing assets with the same names are deleted
+    /// first.
+    async fn upload_assets(&self, release: &ReleaseResponse) -> Result<(), PublishError> {
+        let upload_url = release
+            .upload_url
+            .split_once('{')
+            .map_or(release.upload_url.as_str(), |(url, _)| url);
+        for asset in self.assets {
+            if let Some(existing) = release.assets.iter().find(|a| a.name == asset.name) {
+                let route = format!(
+                    "repos/{}/{}/releases/assets/{}",
+                    self.user, self.repository, existing.id
+                );
+                let request = self.client.request(reqwest::Method::DELETE, &route)?;
+                self.client.send(request).await?;
+            }
+            let request = self
+                .client
+                .request(reqwest::Method::POST, upload_url)?
+                .query(&[("name", &asset.name)])
+                .header(CONTENT_TYPE, &asset.content_type)
+                .body(asset.contents.clone());
+            self.client.send(request).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Publisher for Release<'a> {
+    async fn existing_body(&self) -> Result<Option<String>, PublishError> {
+        match self.release().await {
+            Ok(release) => Ok(Some(release.body.unwrap_or_default())),
+            Err(PublishError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     /// Creates a new release based on the given criteria.
-    pub async fn create(&self) -> octocrab::Result<octocrab::models::repos::Release> {
-        let name = format!("Release {}", self.tag);
-        Octocrab::builder()
-            .personal_token(self.token.to_owned())
-            .build()?
-            .repos(self.user, self.repository)
-            .releases()
-            .create(self.tag)
-            .name(&name)
-            .body(self.description)
-            .send()
-            .await
+    async fn create(&self, body: &str) -> Result<(), PublishError> {
+        let body = ReleaseRequest {
+            tag_name: Some(self.tag),
+            name: Some(self.name),
+            body: Some(body),
+            draft: Some(self.lifecycle.draft),
+            prerelease: Some(self.lifecycle.prerelease),
+            make_latest: self.lifecycle.make_latest,
+        };
+        let release: ReleaseResponse = self
+            .client
+            .json(reqwest::Method::POST, &self.releases_route(), Some(&body))
+            .await?;
+        self.upload_assets(&release).await
     }
 
-    pub async fn release_id(&self) -> octocrab::Result<octocrab::models::ReleaseId> {
-        Ok(Octocrab::builder()
-            .personal_token(self.token.to_owned())
-            .build()?
-            .repos(self.user, self.repository)
-            .releases()
-            .get_by_tag(self.tag)
-            .await?
-            .id)
-    }
-
-    pub async fn update(&self, id: u64) -> octocrab::Result<octocrab::models::repos::Release> {
-        let name = format!("Release {}", self.tag);
-        Octocrab::builder()
-            .personal_token(self.token.to_owned())
-            .build()?
-            .repos(self.user, self.repository)
-            .releases()
-            .update(id)
-            .name(&name)
-            .body(self.description)
-            .send()
-            .await
+    /// Replaces the contents of the release. A draft is only published with `publish_draft`, and
+    /// a prerelease is only changed when it is asked for.
+    async fn update(&self, body: &str) -> Result<(), PublishError> {
+        let id = self.release().await?.id;
+        let body = ReleaseRequest {
+            tag_name: None,
+            name: Some(self.name),
+            body: Some(body),
+            draft: self.lifecycle.draft.then_some(true),
+            prerelease: self.lifecycle.prerelease.then_some(true),
+            make_latest: self.lifecycle.make_latest,
+        };
+        let route = format!("{}/{id}", self.releases_route());
+        let release: ReleaseResponse = self
+            .client
+            .json(reqwest::Method::PATCH, &route, Some(&body))
+            .await?;
+        self.upload_assets(&release).await
+    }
+}
+
+impl<'a> DraftPublisher for Release<'a> {
+    async fn publish_draft(&self) -> Result<bool, PublishError> {
+        let Some(release) = self.find_draft().await? else {
+            return Ok(false);
+        };
+        let body = ReleaseRequest {
+            tag_name: None,
+            name: None,
+            body: None,
+            draft: Some(false),
+            prerelease: None,
+            make_latest: self.lifecycle.make_latest,
+        };
+        let route = format!("{}/{}", self.releases_route(), release.id);
+        let _: ReleaseResponse = self
+            .client
+            .json(reqwest::Method::PATCH, &route, Some(&body))
+            .await?;
+        Ok(true)
+    }
+}
+
+/// Repository queries github for more information about the commits.
+pub struct Repository<'a> {
+    pub client: &'a Client,
+    pub user: &'a str,
+    pub repository: &'a str,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    number: u64,
+    title: String,
+    merged_at: Option<String>,
+    labels: Vec<LabelResponse>,
+    user: Option<UserResponse>,
+}
+
+#[derive(Deserialize)]
+struct UserResponse {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct LabelResponse {
+    name: String,
+}
+
+impl<'a> Repository<'a> {
+    /// Returns the pull requests the given commits were merged with. If a commit is associated
+    /// with more than one pull request, the merged one is preferred. Commits that are not part of
+    /// any pull requests are not included in the result.
+    pub async fn pull_requests(
+        &self,
+        commits: &[git2::Oid],
+    ) -> Result<HashMap<git2::Oid, PullRequest>, PublishError> {
+        let mut res = HashMap::with_capacity(commits.len());
+        for &oid in commits {
+            let route = format!(
+                "repos/{}/{}/commits/{oid}/pulls",
+                self.user, self.repository
+            );
+            let pulls: Vec<PullResponse> = self.client.get(&route).await?;
+            let pull = match pulls.iter().position(|pr| pr.merged_at.is_some()) {
+                Some(i) => pulls.into_iter().nth(i),
+                None => pulls.into_iter().next(),
+            };
+            if let Some(pr) = pull {
+                res.insert(
+                    oid,
+                    PullRequest {
+                        number: pr.number,
+                        title: pr.title,
+                        author: pr.user.map(|u| u.login).unwrap_or_default(),
+                        labels: pr.labels.into_iter().map(|l| l.name).collect(),
+                    },
+                );
+            }
+        }
+        Ok(res)
     }
 }
@@ -0,0 +1,330 @@
+use super::*;
+
+#[cfg(test)]
+mod api_url {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn hosts() {
+        let tcs = vec![
+            ("https://github.com", "https://api.github.com/"),
+            ("https://github.com/", "https://api.github.com/"),
+            (
+                "https://github.example.com",
+                "https://github.example.com/api/v3/",
+            ),
+            (
+                "http://git.example.com:8080/",
+                "http://git.example.com:8080/api/v3/",
+            ),
+        ];
+        for (base_url, want) in tcs {
+            assert_eq!(want, api_url(base_url), "{base_url}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod assets {
+    use super::*;
+    use crate::publish::{self, Mode, Outcome};
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{body_json, body_string, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn assets() -> Vec<Asset> {
+        vec![Asset {
+            name: "app.zip".to_owned(),
+            content_type: "application/zip".to_owned(),
+            contents: b"zip".to_vec(),
+        }]
+    }
+
+    fn release_json(uri: &str, assets: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "id": 1,
+            "tag_name": "v1.0.0",
+            "draft": false,
+            "upload_url": format!("{uri}/upload/releases/1/assets{{?name,label}}"),
+            "assets": assets,
+        })
+    }
+
+    async fn mount_upload(server: &MockServer) {
+        Mock::given(method("POST"))
+            .and(path("/upload/releases/1/assets"))
+            .and(query_param("name", "app.zip"))
+            .and(header("Content-Type", "application/zip"))
+            .and(body_string("zip"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn create() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let uri = server.uri();
+        Mock::given(method("POST"))
+            .and(path("/repos/arsham/shark/releases"))
+            .respond_with(
+                ResponseTemplate::new(201).set_body_json(release_json(&uri, serde_json::json!([]))),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        mount_upload(&server).await;
+
+        let assets = assets();
+        let releaser = Release {
+            client: &Client::new("secret", &uri, ClientOptions::default())?,
+            user: "arsham",
+            repository: "shark",
+            tag: "v1.0.0",
+            name: "Release v1.0.0",
+            lifecycle: Lifecycle::default(),
+            assets: &assets,
+        };
+        assert_eq!(
+            Outcome::Created,
+            publish::publish(&releaser, "notes", Mode::Create).await?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn force_replaces() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let uri = server.uri();
+        let existing = release_json(&uri, serde_json::json!([{"id": 7, "name": "app.zip"}]));
+        Mock::given(method("POST"))
+            .and(path("/repos/arsham/shark/releases"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [{"resource": "Release", "code": "already_exists", "field": "tag_name"}],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases/tags/v1.0.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(existing.clone()))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/arsham/shark/releases/1"))
+            .and(body_json(serde_json::json!({
+                "name": "Release v1.0.0",
+                "body": publish::wrap_notes("notes"),
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(existing))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("DELETE"))
+            .and(path("/repos/arsham/shark/releases/assets/7"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        mount_upload(&server).await;
+
+        let assets = assets();
+        let releaser = Release {
+            client: &Client::new("secret", &uri, ClientOptions::default())?,
+            user: "arsham",
+            repository: "shark",
+            tag: "v1.0.0",
+            name: "Release v1.0.0",
+            lifecycle: Lifecycle::default(),
+            assets: &assets,
+        };
+        assert_eq!(
+            Outcome::Updated,
+            publish::publish(&releaser, "notes", Mode::Force).await?
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod lookup {
+    use super::*;
+    use crate::publish::{self, Mode, Outcome};
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn release_json(id: u64, tag: &str, draft: bool) -> serde_json::Value {
+        serde_json::json!({
+            "id": id,
+            "tag_name": tag,
+            "draft": draft,
+            "body": "old",
+            "upload_url": "https://uploads.example.com/assets{?name,label}",
+        })
+    }
+
+    /// Mounts two pages of releases, where the second page has the draft of `v1.0.0`.
+    async fn mount_pages(server: &MockServer) {
+        let first: Vec<_> = (0..100)
+            .map(|i| release_json(100 + i, &format!("v2.{i}.0"), false))
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(first))
+            .mount(server)
+            .await;
+        let second = vec![
+            release_json(2, "v1.1.0", false),
+            release_json(1, "v1.0.0", true),
+        ];
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(second))
+            .mount(server)
+            .await;
+    }
+
+    fn releaser<'a>(client: &'a Client, tag: &'a str) -> Release<'a> {
+        Release {
+            client,
+            user: "arsham",
+            repository: "shark",
+            tag,
+            name: "Release",
+            lifecycle: Lifecycle::default(),
+            assets: &[],
+        }
+    }
+
+    #[tokio::test]
+    async fn published_by_tag() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases/tags/release%2Fv0.1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(release_json(
+                3,
+                "release/v0.1.0",
+                false,
+            )))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let client = Client::new("secret", &server.uri(), ClientOptions::default())?;
+        let body = releaser(&client, "release/v0.1.0").existing_body().await?;
+        assert_eq!(Some("old".to_owned()), body);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn draft_on_later_page() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases/tags/v1.0.0"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        mount_pages(&server).await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/arsham/shark/releases/1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(release_json(1, "v1.0.0", true)))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let client = Client::new("secret", &server.uri(), ClientOptions::default())?;
+        let release = releaser(&client, "v1.0.0");
+        assert_eq!(
+            Outcome::Updated,
+            publish::publish(&release, "notes", Mode::Upsert).await?
+        );
+        assert!(release.publish_draft().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn missing_release() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/arsham/shark/releases/tags/v0.9.0"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        mount_pages(&server).await;
+
+        let client = Client::new("secret", &server.uri(), ClientOptions::default())?;
+        let release = releaser(&client, "v0.9.0");
+        assert_eq!(None, release.existing_body().await?);
+        assert!(!release.publish_draft().await?);
+        // The published releases are not drafts.
+        assert!(!releaser(&client, "v1.1.0").publish_draft().await?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod client {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn headers_and_routes() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/arsham/shark"))
+            .and(header("Authorization", "Bearer secret"))
+            .and(header("Accept", "application/vnd.github+json"))
+            .and(header("User-Agent", DEFAULT_USER_AGENT))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": 1})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/uploads"))
+            .and(header("User-Agent", "release-bot"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"id": 2})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api_url = format!("{}/api/v3", server.uri());
+        let client = Client::new("secret", &api_url, ClientOptions::default())?;
+        let repo: serde_json::Value = client.get("repos/arsham/shark").await?;
+        assert_eq!(1, repo["id"]);
+
+        let options = ClientOptions {
+            user_agent: Some("release-bot".to_owned()),
+            log: true,
+            ..Default::default()
+        };
+        let client = Client::new("secret", &api_url, options)?;
+        let upload: serde_json::Value = client.get(&format!("{}/uploads", server.uri())).await?;
+        assert_eq!(2, upload["id"]);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_options() {
+        let options = ClientOptions::default();
+        assert!(Client::new("secret", "not a url", options.clone()).is_err());
+        assert!(Client::new("new\nline", DEFAULT_API_URL, options).is_err());
+        let options = ClientOptions {
+            proxy: Some("not a url".to_owned()),
+            ..Default::default()
+        };
+        assert!(Client::new("secret", DEFAULT_API_URL, options).is_err());
+    }
+}
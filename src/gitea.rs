@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use crate::publish::{self, DraftPublisher, Lifecycle, PublishError, Publisher};
+
+#[cfg(test)]
+#[path = "./gitea_test.rs"]
+mod gitea_test;
+
+/// Release publishes the release of a tag to gitea or forgejo through their REST API.
+pub struct Release<'a> {
+    pub token: &'a str,
+    /// The url of the instance, e.g. `https://gitea.example.com`.
+    pub base_url: &'a str,
+    pub owner: &'a str,
+    pub repository: &'a str,
+    pub tag: &'a str,
+    /// The name of the release, see `TagInfo::release_name()`.
+    pub name: &'a str,
+    /// The `make_latest` option is not supported.
+    pub lifecycle: Lifecycle,
+}
+
+#[derive(Serialize)]
+struct ReleaseRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    draft: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prerelease: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    id: u64,
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+impl<'a> Release<'a> {
+    /// Returns the url of the releases of the repository.
+    fn releases_url(&self) -> String {
+        format!(
+            "{}/api/v1/repos/{}/{}/releases",
+            self.base_url.trim_end_matches('/'),
+            self.owner,
+            self.repository
+        )
+    }
+
+    /// Returns the url of the release of the tag.
+    fn tag_url(&self) -> String {
+        format!(
+            "{}/tags/{}",
+            self.releases_url(),
+            publish::encode_segment(self.tag)
+        )
+    }
+
+    fn request(&self, method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+        reqwest::Client::new()
+            .request(method, url)
+            .header("Authorization", format!("token {}", self.token))
+    }
+
+    /// Returns the id of the release of the tag.
+    async fn release_id(&self) -> Result<u64, PublishError> {
+        let request = self.request(reqwest::Method::GET, self.tag_url());
+        let release: ReleaseResponse = publish::send(request).await?.json().await?;
+        Ok(release.id)
+    }
+
+    /// Returns the draft release of the tag. The drafts are listed a page at a time, until the
+    /// draft is found or there are no more pages.
+    async fn find_draft(&self) -> Result<Option<ReleaseResponse>, PublishError> {
+        const LIMIT: usize = 50;
+        for page in 1.. {
+            let request = self
+                .request(reqwest::Method::GET, self.releases_url())
+                .query(&[("draft", "true")])
+                .query(&[("limit", LIMIT), ("page", page)]);
+            let drafts: Vec<ReleaseResponse> = publish::send(request).await?.json().await?;
+            let count = drafts.len();
+            let draft = drafts.into_iter().find(|r| r.tag_name == self.tag);
+            if draft.is_some() || count < LIMIT {
+                return Ok(draft);
+            }
+        }
+        Ok(None)
+    }
+
+    async fn patch(&self, id: u64, body: &ReleaseRequest<'_>) -> Result<(), PublishError> {
+        let url = format!("{}/{id}", self.releases_url());
+        publish::send(self.request(reqwest::Method::PATCH, url).json(body)).await?;
+        Ok(())
+    }
+}
+
+impl<'a> Publisher for Release<'a> {
+    async fn existing_body(&self) -> Result<Option<String>, PublishError> {
+        let release: ReleaseResponse =
+            match publish::send(self.request(reqwest::Method::GET, self.tag_url())).await {
+                Ok(response) => response.json().await?,
+                Err(PublishError::NotFound(_)) => return Ok(None),
+                Err(err) => return Err(err),
+            };
+        Ok(Some(release.body.unwrap_or_default()))
+    }
+
+    async fn create(&self, body: &str) -> Result<(), PublishError> {
+        let body = ReleaseRequest {
+            tag_name: Some(self.tag),
+            name: Some(self.name),
+            body: Some(body),
+            draft: Some(self.lifecycle.draft),
+            prerelease: Some(self.lifecycle.prerelease),
+        };
+        let request = self.request(reqwest::Method::POST, self.releases_url());
+        publish::send(request.json(&body)).await?;
+        Ok(())
+    }
+
+    /// Replaces the contents of the release. A draft is only published with `publish_draft`, and
+    /// a prerelease is only changed when it is asked for.
+    async fn update(&self, body: &str) -> Result<(), PublishError> {
+        let id = self.release_id().await?;
+        let body = ReleaseRequest {
+            tag_name: None,
+            name: Some(self.name),
+            body: Some(body),
+            draft: self.lifecycle.draft.then_some(true),
+            prerelease: self.lifecycle.prerelease.then_some(true),
+        };
+        self.patch(id, &body).await
+    }
+}
+
+impl<'a> DraftPublisher for Release<'a> {
+    async fn publish_draft(&self) -> Result<bool, PublishError> {
+        let Some(draft) = self.find_draft().await? else {
+            return Ok(false);
+        };
+        let body = ReleaseRequest {
+            tag_name: None,
+            name: None,
+            body: None,
+            draft: Some(false),
+            prerelease: None,
+        };
+        self.patch(draft.id, &body).await?;
+        Ok(true)
+    }
+}
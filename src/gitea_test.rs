@@ -0,0 +1,305 @@
+use wiremock::matchers::{body_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+use crate::publish::{self, DraftPublisher, Mode};
+
+const NOTES: &str = "### Fix\n\n- Something";
+const BODY: &str = "<!-- git-release:start -->\n### Fix\n\n- Something\n<!-- git-release:end -->";
+
+fn release(uri: &str) -> Release<'_> {
+    Release {
+        token: "secret",
+        base_url: uri,
+        owner: "arsham",
+        repository: "shark",
+        tag: "v1.0.0",
+        name: "Release v1.0.0",
+        lifecycle: Lifecycle::default(),
+    }
+}
+
+#[cfg(test)]
+mod tag_url {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn encoded_tag() {
+        let mut release = release("https://gitea.example.com/");
+        release.tag = "release/v1.0+1";
+        assert_eq!(
+            "https://gitea.example.com/api/v1/repos/arsham/shark/releases/tags/release%2Fv1.0%2B1",
+            release.tag_url()
+        );
+    }
+}
+
+#[cfg(test)]
+mod publish_release {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn create() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .and(header("Authorization", "token secret"))
+            .and(body_json(serde_json::json!({
+                "tag_name": "v1.0.0",
+                "name": "Release v1.0.0",
+                "body": BODY,
+                "draft": false,
+                "prerelease": false,
+            })))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(serde_json::json!({"id": 1, "tag_name": "v1.0.0"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Create).await?;
+        assert_eq!(publish::Outcome::Created, outcome);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn already_exists() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .respond_with(ResponseTemplate::new(409))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let res = publish::publish(&release(&server.uri()), NOTES, Mode::Create).await;
+        assert!(matches!(res, Err(PublishError::AlreadyExists)), "{res:?}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn force_update() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .respond_with(ResponseTemplate::new(409))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases/tags/v1.0.0"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"id": 42, "tag_name": "v1.0.0"})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/repos/arsham/shark/releases/42"))
+            .and(body_json(serde_json::json!({
+                "name": "Release v1.0.0",
+                "body": BODY,
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"id": 42, "tag_name": "v1.0.0"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Force).await?;
+        assert_eq!(publish::Outcome::Updated, outcome);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod upsert {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn keeps_hand_written_parts() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let existing =
+            "Intro\n\n<!-- git-release:start -->\nold notes\n<!-- git-release:end -->\n\nThanks";
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases/tags/v1.0.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 42,
+                "tag_name": "v1.0.0",
+                "body": existing,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/repos/arsham/shark/releases/42"))
+            .and(body_json(serde_json::json!({
+                "name": "Release v1.0.0",
+                "body": format!("Intro\n\n{BODY}\n\nThanks"),
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Upsert).await?;
+        assert_eq!(publish::Outcome::Updated, outcome);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn creates_missing_release() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases/tags/v1.0.0"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Upsert).await?;
+        assert_eq!(publish::Outcome::Created, outcome);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod lifecycle {
+    use super::*;
+
+    #[tokio::test]
+    async fn create_draft() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .and(body_json(serde_json::json!({
+                "tag_name": "v1.0.0",
+                "name": "Release v1.0.0",
+                "body": BODY,
+                "draft": true,
+                "prerelease": true,
+            })))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let uri = server.uri();
+        let mut release = release(&uri);
+        release.lifecycle.draft = true;
+        release.lifecycle.prerelease = true;
+        publish::publish(&release, NOTES, Mode::Create).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn update_prerelease() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases/tags/v1.0.0"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({"id": 42, "tag_name": "v1.0.0"})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/repos/arsham/shark/releases/42"))
+            .and(body_json(serde_json::json!({
+                "name": "Release v1.0.0",
+                "body": BODY,
+                "prerelease": true,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let uri = server.uri();
+        let mut release = release(&uri);
+        release.lifecycle.prerelease = true;
+        release.update(BODY).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn publish_draft() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .and(query_param("draft", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                {"id": 41, "tag_name": "v0.9.0"},
+                {"id": 42, "tag_name": "v1.0.0"},
+            ])))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/repos/arsham/shark/releases/42"))
+            .and(body_json(serde_json::json!({"draft": false})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let uri = server.uri();
+        assert!(release(&uri).publish_draft().await?);
+        let mut other = release(&uri);
+        other.tag = "v2.0.0";
+        assert!(!other.publish_draft().await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn draft_on_later_page() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let drafts: Vec<_> = (0..50)
+            .map(|id| serde_json::json!({"id": id, "tag_name": format!("v0.{id}.0")}))
+            .collect();
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .and(query_param("draft", "true"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(drafts))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/repos/arsham/shark/releases"))
+            .and(query_param("draft", "true"))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([{"id": 142, "tag_name": "v1.0.0"}])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/api/v1/repos/arsham/shark/releases/142"))
+            .and(body_json(serde_json::json!({"draft": false})))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(release(&server.uri()).publish_draft().await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use crate::publish::{self, PublishError, Publisher};
+
+#[cfg(test)]
+#[path = "./gitlab_test.rs"]
+mod gitlab_test;
+
+/// Release publishes the release of a tag to gitlab through its Releases API.
+pub struct Release<'a> {
+    pub token: &'a str,
+    /// The url of the gitlab instance, e.g. `https://gitlab.com`.
+    pub base_url: &'a str,
+    /// The path of the project including its groups, e.g. `group/subgroup/project`.
+    pub project: &'a str,
+    pub tag: &'a str,
+    /// The name of the release, see `TagInfo::release_name()`.
+    pub name: &'a str,
+}
+
+#[derive(Serialize)]
+struct ReleaseRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_name: Option<&'a str>,
+    name: &'a str,
+    description: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ReleaseResponse {
+    description: Option<String>,
+}
+
+impl<'a> Release<'a> {
+    /// Returns the url of the releases of the project.
+    fn releases_url(&self) -> String {
+        format!(
+            "{}/api/v4/projects/{}/releases",
+            self.base_url.trim_end_matches('/'),
+            publish::encode_segment(self.project)
+        )
+    }
+
+    /// Returns the url of the release of the tag.
+    fn release_url(&self) -> String {
+        format!(
+            "{}/{}",
+            self.releases_url(),
+            publish::encode_segment(self.tag)
+        )
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        url: String,
+        tag_name: Option<&str>,
+        description: &str,
+    ) -> Result<(), PublishError> {
+        let body = ReleaseRequest {
+            tag_name,
+            name: self.name,
+            description,
+        };
+        let request = reqwest::Client::new()
+            .request(method, url)
+            .header("PRIVATE-TOKEN", self.token)
+            .json(&body);
+        publish::send(request).await?;
+        Ok(())
+    }
+}
+
+impl<'a> Publisher for Release<'a> {
+    async fn existing_body(&self) -> Result<Option<String>, PublishError> {
+        let url = self.release_url();
+        let request = reqwest::Client::new()
+            .get(url)
+            .header("PRIVATE-TOKEN", self.token);
+        let release: ReleaseResponse = match publish::send(request).await {
+            Ok(response) => response.json().await?,
+            Err(PublishError::NotFound(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        Ok(Some(release.description.unwrap_or_default()))
+    }
+
+    async fn create(&self, body: &str) -> Result<(), PublishError> {
+        let url = self.releases_url();
+        self.send(reqwest::Method::POST, url, Some(self.tag), body)
+            .await
+    }
+
+    async fn update(&self, body: &str) -> Result<(), PublishError> {
+        self.send(reqwest::Method::PUT, self.release_url(), None, body)
+            .await
+    }
+}
@@ -0,0 +1,235 @@
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+use crate::publish::Mode;
+
+const NOTES: &str = "### Fix\n\n- Something";
+const BODY: &str = "<!-- git-release:start -->\n### Fix\n\n- Something\n<!-- git-release:end -->";
+
+fn release(uri: &str) -> Release<'_> {
+    Release {
+        token: "secret",
+        base_url: uri,
+        project: "arsham/shark",
+        tag: "v1.0.0",
+        name: "Release v1.0.0",
+    }
+}
+
+#[cfg(test)]
+mod releases_url {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn nested_groups() {
+        let release = Release {
+            token: "token",
+            base_url: "https://gitlab.example.com/",
+            project: "group/sub/project",
+            tag: "v1.0.0",
+            name: "Release v1.0.0",
+        };
+        assert_eq!(
+            "https://gitlab.example.com/api/v4/projects/group%2Fsub%2Fproject/releases",
+            release.releases_url()
+        );
+    }
+
+    #[test]
+    fn encoded_tag() {
+        let mut release = release("https://gitlab.example.com");
+        release.tag = "release/v1.0+1";
+        assert_eq!(
+            "https://gitlab.example.com/api/v4/projects/arsham%2Fshark/releases/release%2Fv1.0%2B1",
+            release.release_url()
+        );
+    }
+}
+
+#[cfg(test)]
+mod publish_release {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn create() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases"))
+            .and(header("PRIVATE-TOKEN", "secret"))
+            .and(body_json(serde_json::json!({
+                "tag_name": "v1.0.0",
+                "name": "Release v1.0.0",
+                "description": BODY,
+            })))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Create).await?;
+        assert_eq!(publish::Outcome::Created, outcome);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn already_exists() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases"))
+            .respond_with(
+                ResponseTemplate::new(409)
+                    .set_body_json(serde_json::json!({"message": "Release already exists"})),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let res = publish::publish(&release(&server.uri()), NOTES, Mode::Create).await;
+        assert!(matches!(res, Err(PublishError::AlreadyExists)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn force_update() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases"))
+            .respond_with(ResponseTemplate::new(409))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(
+                "/api/v4/projects/arsham%2Fshark/releases/release%2Fv1.0%2B1",
+            ))
+            .and(header("PRIVATE-TOKEN", "secret"))
+            .and(body_json(serde_json::json!({
+                "name": "Release v1.0.0",
+                "description": BODY,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let uri = server.uri();
+        let mut release = release(&uri);
+        release.tag = "release/v1.0+1";
+        let outcome = publish::publish(&release, NOTES, Mode::Force).await?;
+        assert_eq!(publish::Outcome::Updated, outcome);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn error_status() -> Result<(), Box<dyn std::error::Error>> {
+        let tcs = [(401, "401 Unauthorized"), (404, "404 Project Not Found")];
+        for (status, message) in tcs {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/api/v4/projects/arsham%2Fshark/releases"))
+                .respond_with(
+                    ResponseTemplate::new(status)
+                        .set_body_json(serde_json::json!({"message": message})),
+                )
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let res = publish::publish(&release(&server.uri()), NOTES, Mode::Force).await;
+            match (status, res) {
+                (401, Err(PublishError::Unauthorized(got))) => assert_eq!(message, got),
+                (404, Err(PublishError::NotFound(got))) => assert_eq!(message, got),
+                (_, res) => panic!("unexpected result for {status}: {res:?}"),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod upsert {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn keeps_hand_written_parts() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        let existing =
+            "Intro\n\n<!-- git-release:start -->\nold notes\n<!-- git-release:end -->\n\nThanks";
+        Mock::given(method("GET"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases/v1.0.0"))
+            .and(header("PRIVATE-TOKEN", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "tag_name": "v1.0.0",
+                "description": existing,
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases/v1.0.0"))
+            .and(body_json(serde_json::json!({
+                "name": "Release v1.0.0",
+                "description": format!("Intro\n\n{BODY}\n\nThanks"),
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Upsert).await?;
+        assert_eq!(publish::Outcome::Updated, outcome);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn creates_missing_release() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases/v1.0.0"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let outcome = publish::publish(&release(&server.uri()), NOTES, Mode::Upsert).await?;
+        assert_eq!(publish::Outcome::Created, outcome);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unauthorized_lookup() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v4/projects/arsham%2Fshark/releases/v1.0.0"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let res = publish::publish(&release(&server.uri()), NOTES, Mode::Upsert).await;
+        assert!(matches!(res, Err(PublishError::Unauthorized(_))));
+        Ok(())
+    }
+}
@@ -1,16 +1,40 @@
+use std::cell::OnceCell;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
 use colored::*;
 
 use args::Tag;
+use asset::Asset;
+use config::{Config, GithubRelease, GroupBy, Merges, UnknownScope};
+use publish::{Backend, DraftPublisher, Lifecycle, Mode, Outcome, Publisher};
+use workspace::commit::Commit;
+use workspace::errors::GRError;
 use workspace::release::Release;
+use workspace::remote::{Provider, RemoteInfo};
+use workspace::repository::Repository;
 
 mod args;
+mod asset;
+mod config;
+mod diff;
 mod gh;
+mod gitea;
+mod gitlab;
+mod publish;
+mod retry;
 mod workspace;
 
 #[cfg(test)]
 mod common_test;
 
+#[cfg(test)]
+#[path = "./main_test.rs"]
+mod main_test;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let opt = args::Opt::new();
@@ -22,59 +46,396 @@ async fn main() -> Result<()> {
             env!("CURRENT_SHA")
         );
     }
+    release(opt, Path::new(".")).await
+}
 
-    let repo = workspace::repository::Repository::new(".")?;
+/// Prints the release notes of the tags in the repository at `dir`, or publishes them based on
+/// the options.
+async fn release(opt: args::Opt, dir: &Path) -> Result<()> {
+    // The client of github.com, or the GitHub Enterprise Server. It is made once, when it is
+    // first needed.
+    let github = OnceCell::new();
+    let Notes {
+        repo,
+        config,
+        latest,
+        description,
+    } = notes(&opt, dir, &github).await?;
+    let publish_draft = matches!(opt.sub_commands, Some(args::Command::PublishDraft));
+    if !opt.publish && !publish_draft {
+        println!("{description}");
+        return Ok(());
+    }
+
+    let remote = || repo.remote_info(&opt.remote);
+    let description = &description;
+    let lifecycle = Lifecycle {
+        draft: opt.draft,
+        prerelease: opt.prerelease,
+        make_latest: opt.make_latest,
+    };
+    let mode = if opt.upsert {
+        Mode::Upsert
+    } else if opt.force {
+        Mode::Force
+    } else {
+        Mode::Create
+    };
+    let review = match (opt.diff, opt.yes) {
+        (false, _) => Review::Skip,
+        (true, false) => Review::Confirm,
+        (true, true) => Review::Show,
+    };
+    let assets = Asset::load(&opt.assets, opt.checksums)?;
+    let remote = remote()?;
+    let name = &repo
+        .tag_info(&latest)?
+        .release_name(config.name_template(), &remote.repo);
+    let backend = Backend::detect(
+        &remote,
+        &[
+            (Backend::GitHub, opt.github_host.as_deref()),
+            (Backend::GitLab, opt.gitlab_url.as_deref()),
+            (Backend::Gitea, opt.gitea_url.as_deref()),
+        ],
+    )?;
+    // The url of the self-hosted instance, or the web server of the remote.
+    let base_url = |url: &Option<String>| url.clone().unwrap_or_else(|| remote.base_url());
+    let outcome = match backend {
+        Backend::GitHub => {
+            let releaser = gh::Release {
+                client: github_client(&opt, &github, &remote)?,
+                user: &remote.owner,
+                repository: &remote.repo,
+                tag: &latest,
+                name,
+                lifecycle,
+                assets: &assets,
+            };
+            run(&releaser, description, mode, review, publish_draft, &latest).await?
+        },
+        Backend::GitLab => {
+            if publish_draft || !lifecycle.is_default() {
+                return Err(
+                    GRError::Unsupported("Drafts, prereleases and make-latest", "gitlab").into(),
+                );
+            }
+            if !assets.is_empty() {
+                return Err(GRError::Unsupported("Uploading assets", "gitlab").into());
+            }
+            let token = opt
+                .gitlab_token
+                .as_deref()
+                .ok_or(GRError::MissingToken("GITLAB_TOKEN"))?;
+            let releaser = gitlab::Release {
+                token,
+                base_url: &base_url(&opt.gitlab_url),
+                project: &format!("{}/{}", remote.owner, remote.repo),
+                tag: &latest,
+                name,
+            };
+            publish_release(&releaser, description, mode, review, &latest).await?
+        },
+        Backend::Gitea => {
+            if lifecycle.make_latest.is_some() {
+                return Err(GRError::Unsupported("The make-latest option", "gitea").into());
+            }
+            if !assets.is_empty() {
+                return Err(GRError::Unsupported("Uploading assets", "gitea").into());
+            }
+            let token = opt
+                .gitea_token
+                .as_deref()
+                .ok_or(GRError::MissingToken("GITEA_TOKEN"))?;
+            let releaser = gitea::Release {
+                token,
+                base_url: &base_url(&opt.gitea_url),
+                owner: &remote.owner,
+                repository: &remote.repo,
+                tag: &latest,
+                name,
+                lifecycle,
+            };
+            run(&releaser, description, mode, review, publish_draft, &latest).await?
+        },
+    };
+    match outcome {
+        Outcome::Created => {},
+        Outcome::Updated if mode == Mode::Upsert => {
+            println!("Updated the release of the {} tag", latest.green().bold())
+        },
+        Outcome::Updated => println!("Force updated the {} tag", latest.green().bold()),
+        Outcome::PublishedDraft => {
+            println!(
+                "Published the draft release of the {} tag",
+                latest.green().bold()
+            )
+        },
+    }
+    Ok(())
+}
+
+/// Notes are the release notes of the latest tag, with what they were made from.
+struct Notes {
+    repo: Repository,
+    config: Rc<Config>,
+    /// The tag of the release.
+    latest: String,
+    description: String,
+}
+
+/// Returns the release notes of the tags in the repository at `dir`, based on the options.
+async fn notes(opt: &args::Opt, dir: &Path, github: &OnceCell<gh::Client>) -> Result<Notes> {
+    let mut config = match opt.config {
+        Some(ref path) => Config::load(path, true)?,
+        None => Config::load(dir.join(config::DEFAULT_PATH), false)?,
+    };
+
+    let repo = Repository::new(dir)?.with_merges(config.merges);
     let latest: String;
     let prev: String;
-    match opt.tags {
+    match &opt.tags {
         Tag::None => {
             latest = repo.latest_tag().context("getting latest tag")?;
             prev = repo.previous_tag(&latest)?;
         },
         Tag::From(tag) => {
-            repo.validate_tag(&tag)?;
+            repo.validate_tag(tag)?;
             latest = repo.latest_tag()?;
-            prev = tag;
+            prev = tag.clone();
         },
         Tag::Single(tag) => {
-            repo.validate_tag(&tag)?;
-            latest = tag;
+            repo.validate_tag(tag)?;
+            latest = tag.clone();
             prev = repo.previous_tag(&latest)?;
         },
         Tag::Range(from, to) => {
-            repo.validate_tag(&from)?;
-            repo.validate_tag(&to)?;
-            latest = to;
-            prev = from;
+            repo.validate_tag(from)?;
+            repo.validate_tag(to)?;
+            latest = to.clone();
+            prev = from.clone();
         },
     }
 
-    let token = &std::env::var("GITHUB_TOKEN")?;
-    let user = &repo.username(&opt.remote)?;
-    let repo_name = &repo.repo_name(&opt.remote)?;
-    let commits = repo.commits_between_tags(&prev, &latest)?;
-    let release: Release = commits.collect::<Vec<git2::Commit>>().into();
+    let paths = match config.github_release {
+        true => config::GITHUB_RELEASE_PATHS.as_slice(),
+        false => &[],
+    };
+    for path in paths {
+        if let Some(contents) = repo.file_contents(&latest, path)? {
+            let github_release: GithubRelease = contents.parse().context(*path)?;
+            let group_by = config.group_by;
+            config.merge_github_release(github_release);
+            // The labels can't be fetched without a token, e.g. in the forks.
+            if group_by.is_none() && opt.github_token.is_none() {
+                eprintln!(
+                    "{} GITHUB_TOKEN is not set, the release is grouped by verbs instead of \
+                     the labels in {path}",
+                    "Warning:".yellow().bold()
+                );
+                config.group_by = group_by;
+            }
+            break;
+        }
+    }
+    if config.links {
+        match repo.remote_info(&opt.remote) {
+            Ok(remote) => config.remote = Some(remote),
+            Err(err) => eprintln!("{} no links are added: {err}", "Warning:".yellow().bold()),
+        }
+    }
+    let config = Rc::new(config);
 
-    if !opt.publish {
-        println!("{release}");
-        return Ok(());
+    let remote = || repo.remote_info(&opt.remote);
+    let mut commits = vec![];
+    for commit in repo.commits_between_tags(&prev, &latest)? {
+        let branch = match config.merges {
+            Merges::PullRequests => repo.branch_commits(&commit)?,
+            _ => vec![],
+        };
+        let mut commit = Commit::new(commit, Rc::clone(&config));
+        commit.set_branch(&branch);
+        commits.push(commit);
+    }
+    let mut release = Release::new(commits, Rc::clone(&config));
+    let patch_ids = repo.patch_ids(&release.commit_ids())?;
+    release.remove_duplicates(&patch_ids);
+    for reference in &opt.released_in {
+        let released = repo
+            .released_patch_ids(reference, &prev)
+            .with_context(|| format!("getting the released commits of {reference}"))?;
+        release.remove_released(&patch_ids, &released);
     }
 
-    let releaser = gh::Release {
-        token,
-        user,
-        repository: repo_name,
-        tag: &latest,
-        description: &format!("{release}"),
-    };
+    if opt.pull_requests || config.group_by() == GroupBy::Label {
+        let ids = release.commit_ids();
+        let remote = remote()?;
+        // The pull requests are only fetched from github, which is on another host if it is
+        // given explicitly.
+        if remote.provider != Provider::GitHub && opt.github_host.is_none() {
+            let provider = remote.provider.name();
+            return Err(GRError::Unsupported("Pull request enrichment", provider).into());
+        }
+        let gh_repo = gh::Repository {
+            client: github_client(opt, github, &remote)?,
+            user: &remote.owner,
+            repository: &remote.repo,
+        };
+        let pull_requests = gh_repo
+            .pull_requests(&ids)
+            .await
+            .context("getting pull requests")?;
+        release.set_pull_requests(pull_requests);
+    }
 
-    if let Err(err) = releaser.create().await {
-        if !opt.force {
-            return Err(err.into());
+    let unknown_scopes = release
+        .unknown_scopes()
+        .into_iter()
+        .map(|(scope, commit)| format!("'{scope}' in {}", commit.short_id()))
+        .collect::<Vec<String>>();
+    if !unknown_scopes.is_empty() {
+        let unknown_scopes = unknown_scopes.join(", ");
+        match config.scopes.unknown {
+            UnknownScope::Warn => {
+                eprintln!(
+                    "{} unknown scopes: {unknown_scopes}",
+                    "Warning:".yellow().bold()
+                )
+            },
+            UnknownScope::Reject => return Err(GRError::UnknownScopes(unknown_scopes).into()),
         }
-        let id = releaser.release_id().await?;
-        releaser.update(id.0).await?;
-        println!("Force updated the {} tag", latest.green().bold());
     }
-    Ok(())
+
+    let mut description = format!("{release}");
+    if let Some(remote) = config.links() {
+        let compare = remote.compare_url(&prev, &latest);
+        description.push_str(&format!("\n\n**Full Changelog**: {compare}"));
+    }
+    // The commits of the release borrow the repository.
+    drop(release);
+    Ok(Notes {
+        repo,
+        config,
+        latest,
+        description,
+    })
+}
+
+/// Returns the client of github.com, or the GitHub Enterprise Server, which is made the first time
+/// it is needed.
+fn github_client<'a>(
+    opt: &args::Opt,
+    github: &'a OnceCell<gh::Client>,
+    remote: &RemoteInfo,
+) -> Result<&'a gh::Client> {
+    if let Some(client) = github.get() {
+        return Ok(client);
+    }
+    let token = opt
+        .github_token
+        .as_deref()
+        .ok_or(GRError::MissingToken("GITHUB_TOKEN"))?;
+    let base_url = match opt.github_host {
+        Some(ref host) if host.contains("://") => host.clone(),
+        Some(ref host) => format!("https://{host}"),
+        None => remote.base_url(),
+    };
+    let options = gh::ClientOptions {
+        user_agent: opt.user_agent.clone(),
+        proxy: opt.proxy.clone(),
+        retry: retry::Retry {
+            max_retries: opt.retries,
+            max_delay: Duration::from_secs(opt.max_retry_delay),
+            ..Default::default()
+        },
+        log: opt.verbose,
+    };
+    let client = gh::Client::new(token, &gh::api_url(&base_url), options)?;
+    Ok(github.get_or_init(|| client))
+}
+
+/// Review decides if the changes to an existing release are shown before it is overwritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Review {
+    Skip,
+    /// The changes are shown, and they should be confirmed.
+    Confirm,
+    /// The changes are shown and applied.
+    Show,
+}
+
+/// Publishes the draft release of the tag if `publish_draft` is set, otherwise publishes the
+/// release with the notes based on the `mode`.
+async fn run<P: DraftPublisher>(
+    publisher: &P,
+    notes: &str,
+    mode: Mode,
+    review: Review,
+    publish_draft: bool,
+    tag: &str,
+) -> Result<Outcome> {
+    if !publish_draft {
+        return publish_release(publisher, notes, mode, review, tag).await;
+    }
+    if !publisher.publish_draft().await? {
+        return Err(GRError::DraftNotFound(tag.to_owned()).into());
+    }
+    Ok(Outcome::PublishedDraft)
+}
+
+/// Publishes the release with the notes based on the `mode`. If the existing release would be
+/// overwritten, the changes are reviewed first.
+async fn publish_release<P: Publisher>(
+    publisher: &P,
+    notes: &str,
+    mode: Mode,
+    review: Review,
+    tag: &str,
+) -> Result<Outcome> {
+    if review != Review::Skip && mode != Mode::Create {
+        confirm_changes(publisher, notes, mode, review, tag).await?;
+    }
+    Ok(publish::publish(publisher, notes, mode).await?)
+}
+
+/// Prints the diff of the body of the existing release and its new body. If there are changes
+/// and they should be confirmed, the user is asked on the terminal. Without a terminal, e.g. in
+/// CI, an `Err` is returned.
+async fn confirm_changes<P: Publisher>(
+    publisher: &P,
+    notes: &str,
+    mode: Mode,
+    review: Review,
+    tag: &str,
+) -> Result<()> {
+    let Some(existing) = publisher.existing_body().await? else {
+        return Ok(());
+    };
+    let existing = existing.replace("\r\n", "\n");
+    let body = match mode {
+        Mode::Upsert => publish::merge_body(&existing, notes),
+        _ => publish::wrap_notes(notes),
+    };
+    let diff = diff::unified(&existing, &body);
+    if diff.is_empty() {
+        println!(
+            "The release of the {} tag is up to date",
+            tag.green().bold()
+        );
+        return Ok(());
+    }
+    print!("{}", diff::colorize(&diff));
+    if review == Review::Show {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(GRError::ReleaseChanged(tag.to_owned()).into());
+    }
+    eprint!("Apply the changes to the release of the {tag} tag? [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" => Ok(()),
+        _ => Err(GRError::Aborted.into()),
+    }
 }
@@ -0,0 +1,307 @@
+use structopt::StructOpt;
+use tempfile::TempDir;
+use wiremock::matchers::{body_partial_json, header, method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+use crate::common_test;
+use crate::publish::PublishError;
+
+const RELEASES: &str = "/api/v3/repos/arsham/shark/releases";
+
+/// Returns a repository with the `v0.1.0` and `v0.2.0` tags, and a github remote.
+fn repository() -> Result<TempDir, Box<dyn std::error::Error>> {
+    let (dir, _) = common_test::repo_init();
+    let repo = git2::Repository::open(&dir)?;
+    repo.remote("origin", "git@github.com:arsham/shark.git")?;
+    common_test::commit_tag(&repo, "file1", "v0.1.0");
+    let oid = common_test::commit_file(&repo, "file2", "contents", "feat: add the fins");
+    common_test::tag(&repo, oid, "v0.2.0");
+    Ok(dir)
+}
+
+/// Returns the options for publishing to the mock server, with the extra arguments.
+fn options(server: &MockServer, args: &[&str]) -> args::Opt {
+    let uri = server.uri();
+    let mut all = vec![
+        "git-release",
+        "--publish",
+        "--github-token",
+        "secret",
+        "--github-host",
+        &uri,
+        "--retries",
+        "0",
+    ];
+    all.extend(args);
+    args::Opt::from_iter(all).with_tags()
+}
+
+fn release_json(server: &MockServer) -> serde_json::Value {
+    serde_json::json!({
+        "id": 1,
+        "tag_name": "v0.2.0",
+        "draft": false,
+        "upload_url": format!("{}/uploads{{?name,label}}", server.uri()),
+    })
+}
+
+async fn mount_create(server: &MockServer, response: ResponseTemplate) {
+    Mock::given(method("POST"))
+        .and(path(RELEASES))
+        .and(header("Authorization", "Bearer secret"))
+        .and(body_partial_json(serde_json::json!({
+            "tag_name": "v0.2.0",
+            "name": "Release v0.2.0",
+            "draft": false,
+            "prerelease": false,
+        })))
+        .respond_with(response)
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+fn already_exists() -> ResponseTemplate {
+    ResponseTemplate::new(422).set_body_json(serde_json::json!({
+        "message": "Validation Failed",
+        "errors": [{"resource": "Release", "code": "already_exists", "field": "tag_name"}],
+    }))
+}
+
+/// Returns the error of publishing as a `PublishError`.
+fn publish_error(res: Result<()>) -> PublishError {
+    match res {
+        Ok(()) => panic!("expected an error"),
+        Err(err) => err
+            .downcast::<PublishError>()
+            .unwrap_or_else(|err| panic!("unexpected error: {err:?}")),
+    }
+}
+
+#[cfg(test)]
+mod publish_github {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn create() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository()?;
+        let server = MockServer::start().await;
+        let response = ResponseTemplate::new(201).set_body_json(release_json(&server));
+        mount_create(&server, response).await;
+
+        release(options(&server, &[]), dir.path()).await?;
+
+        let requests = server.received_requests().await.unwrap_or_default();
+        assert_eq!(1, requests.len());
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body)?;
+        let want = publish::wrap_notes("### Feature\n\n- Add the fins");
+        assert_eq!(want, body["body"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conflict_then_force_update() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository()?;
+        let server = MockServer::start().await;
+        mount_create(&server, already_exists()).await;
+        Mock::given(method("GET"))
+            .and(path(format!("{RELEASES}/tags/v0.2.0")))
+            .respond_with(ResponseTemplate::new(200).set_body_json(release_json(&server)))
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path(format!("{RELEASES}/1")))
+            .and(body_partial_json(
+                serde_json::json!({"name": "Release v0.2.0"}),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(release_json(&server)))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        release(options(&server, &["--force"]), dir.path()).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn conflict_without_force() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository()?;
+        let server = MockServer::start().await;
+        mount_create(&server, already_exists()).await;
+
+        let err = publish_error(release(options(&server, &[]), dir.path()).await);
+        assert!(matches!(err, PublishError::AlreadyExists), "{err:?}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn not_found() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository()?;
+        let server = MockServer::start().await;
+        let response = ResponseTemplate::new(404).set_body_json(serde_json::json!({
+            "message": "Not Found",
+        }));
+        mount_create(&server, response).await;
+
+        let err = publish_error(release(options(&server, &[]), dir.path()).await);
+        assert!(
+            matches!(err, PublishError::NotFound(ref m) if m == "Not Found"),
+            "{err:?}"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unauthorized() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository()?;
+        let server = MockServer::start().await;
+        let response = ResponseTemplate::new(401).set_body_json(serde_json::json!({
+            "message": "Bad credentials",
+        }));
+        mount_create(&server, response).await;
+        Mock::given(method("PATCH"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        let err = publish_error(release(options(&server, &["--force"]), dir.path()).await);
+        assert!(matches!(err, PublishError::Unauthorized(_)), "{err:?}");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod github_release {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Returns a repository with a `.github/release.yml` file at the `v0.2.0` tag.
+    fn repository(config: &str) -> Result<TempDir, Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        repo.remote("origin", "git@github.com:arsham/shark.git")?;
+        common_test::commit_tag(&repo, "file1", "v0.1.0");
+        let release_yml = "changelog:\n  categories:\n    - title: All\n      labels: ['*']";
+        common_test::commit_file(&repo, ".github/release.yml", release_yml, "ci: add labels");
+        let oid = common_test::commit_file(&repo, "file2", "contents", "feat: add the fins");
+        common_test::tag(&repo, oid, "v0.2.0");
+        std::fs::write(dir.path().join(config::DEFAULT_PATH), config)?;
+        Ok(dir)
+    }
+
+    /// Returns the options for printing the release, without a github token.
+    fn print_options() -> args::Opt {
+        let mut opt = args::Opt::from_iter(["git-release"]).with_tags();
+        opt.github_token = None;
+        opt
+    }
+
+    /// Returns the notes of the repository, which is made with the config.
+    async fn description(config: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let dir = repository(config)?;
+        let notes = notes(&print_options(), dir.path(), &OnceCell::new()).await?;
+        Ok(notes.description)
+    }
+
+    #[tokio::test]
+    async fn not_used_by_default() -> Result<(), Box<dyn std::error::Error>> {
+        let want = "### Feature\n\n- Add the fins\n\n### CI\n\n- Add labels";
+        assert_eq!(want, description("").await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn no_token() -> Result<(), Box<dyn std::error::Error>> {
+        let want = "### Feature\n\n- Add the fins\n\n### CI\n\n- Add labels";
+        assert_eq!(want, description("github_release: true").await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn with_token() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository("github_release: true")?;
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path_regex(
+                r"^/api/v3/repos/arsham/shark/commits/\w+/pulls$",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let notes = notes(&options(&server, &[]), dir.path(), &OnceCell::new()).await?;
+        assert_eq!("### All\n\n- Add labels\n- Add the fins", notes.description);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn explicit_label_grouping() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = repository("github_release: true\ngroup_by: label")?;
+        let res = release(print_options(), dir.path()).await;
+        let err = res.expect_err("the labels need a token");
+        assert!(
+            matches!(
+                err.downcast_ref::<GRError>(),
+                Some(GRError::MissingToken("GITHUB_TOKEN"))
+            ),
+            "{err:?}"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod pull_requests {
+    use super::*;
+
+    #[tokio::test]
+    async fn other_providers() -> Result<(), Box<dyn std::error::Error>> {
+        let tcs = [
+            ("git@gitlab.com:arsham/shark.git", "gitlab"),
+            ("https://codeberg.org/arsham/shark", "gitea"),
+            ("git@bitbucket.org:arsham/shark.git", "bitbucket"),
+            ("https://git.example.com/arsham/shark", "unrecognised hosts"),
+        ];
+        for (url, want) in tcs {
+            let dir = repository()?;
+            let repo = git2::Repository::open(&dir)?;
+            repo.remote_set_url("origin", url)?;
+            let mut opt = args::Opt::from_iter(["git-release", "--pull-requests"]).with_tags();
+            opt.github_token = Some("secret".to_owned());
+            let err = release(opt, dir.path()).await.expect_err(url);
+            assert!(
+                matches!(
+                    err.downcast_ref::<GRError>(),
+                    Some(GRError::Unsupported("Pull request enrichment", provider)) if *provider == want
+                ),
+                "{url}: {err:?}"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod options {
+    use super::*;
+
+    #[test]
+    fn yes_requires_diff() {
+        assert!(args::Opt::from_iter_safe(["git-release", "-p", "-f", "--yes"]).is_err());
+        assert!(args::Opt::from_iter_safe(["git-release", "-p", "-f", "--diff", "--yes"]).is_ok());
+    }
+
+    #[test]
+    fn diff_requires_update() {
+        assert!(args::Opt::from_iter_safe(["git-release", "-p", "--diff"]).is_err());
+        for update in ["--force", "--upsert"] {
+            assert!(args::Opt::from_iter_safe(["git-release", "-p", update, "--diff"]).is_ok());
+        }
+        let opt = args::Opt::from_iter_safe(["git-release", "-f", "--upsert", "--diff"]);
+        assert!(opt.is_ok());
+    }
+}
@@ -0,0 +1,336 @@
+use std::str::FromStr;
+
+use reqwest::StatusCode;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::workspace::errors::GRError;
+use crate::workspace::remote::{Provider, RemoteInfo};
+
+#[cfg(test)]
+#[path = "./publish_test.rs"]
+mod publish_test;
+
+/// Backend is the service that hosts the repository, where the releases are published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    GitHub,
+    GitLab,
+    /// Gitea, and its fork Forgejo.
+    Gitea,
+}
+
+impl Backend {
+    /// Returns the backend of the remote. Self-hosted instances are recognised by the urls that
+    /// are given for each backend, or by the provider of the remote. The remotes of other
+    /// providers are only published to github if its host is given explicitly, e.g. for a GitHub
+    /// Enterprise Server with a different ssh host.
+    ///
+    /// # Errors
+    ///
+    /// If the provider of the remote doesn't have a backend, an `Err` is returned.
+    pub fn detect(
+        remote: &RemoteInfo,
+        self_hosted: &[(Backend, Option<&str>)],
+    ) -> Result<Self, GRError> {
+        let backend = self_hosted
+            .iter()
+            .find(|(_, url)| url.is_some_and(|url| url_host(url) == remote.host));
+        if let Some(&(backend, _)) = backend {
+            return Ok(backend);
+        }
+        let github_host = self_hosted
+            .iter()
+            .any(|&(backend, url)| backend == Backend::GitHub && url.is_some());
+        match remote.provider {
+            Provider::GitHub => Ok(Backend::GitHub),
+            Provider::GitLab => Ok(Backend::GitLab),
+            Provider::Gitea => Ok(Backend::Gitea),
+            _ if github_host => Ok(Backend::GitHub),
+            Provider::Bitbucket => Err(GRError::Unsupported("Publishing", "bitbucket")),
+            Provider::AzureDevOps => Err(GRError::Unsupported("Publishing", "azure devops")),
+            Provider::Other => Err(GRError::UnknownBackend(remote.host.clone())),
+        }
+    }
+}
+
+/// Returns the host of the url, e.g. `gitlab.example.com` in `https://gitlab.example.com:8443/`.
+fn url_host(url: &str) -> &str {
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    url.split(['/', ':']).next().unwrap_or(url)
+}
+
+/// Returns the value percent-encoded to be used as one segment of a url path, e.g. a tag like
+/// `release/v1.0+1` becomes `release%2Fv1.0%2B1`.
+pub fn encode_segment(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            },
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Lifecycle decides the state of the new releases.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Lifecycle {
+    /// Creates the release as a draft, which is published later with the `publish-draft`
+    /// command.
+    pub draft: bool,
+    pub prerelease: bool,
+    /// Decides if the release is marked as the latest release. This is only supported by github.
+    pub make_latest: Option<MakeLatest>,
+}
+
+impl Lifecycle {
+    /// Returns true if none of the options are set.
+    pub fn is_default(&self) -> bool {
+        !self.draft && !self.prerelease && self.make_latest.is_none()
+    }
+}
+
+/// MakeLatest decides if a github release is marked as the latest release. With `legacy`, the
+/// latest release is decided by the creation date and the semantic version of the tags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MakeLatest {
+    True,
+    False,
+    Legacy,
+}
+
+impl FromStr for MakeLatest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "true" => Ok(MakeLatest::True),
+            "false" => Ok(MakeLatest::False),
+            "legacy" => Ok(MakeLatest::Legacy),
+            _ => Err(format!(
+                "invalid value: {s}, expected true, false or legacy"
+            )),
+        }
+    }
+}
+
+/// The markers around the generated notes in the body of a release. Anything outside of them is
+/// kept when the release is updated with `Mode::Upsert`.
+pub const START_MARKER: &str = "<!-- git-release:start -->";
+pub const END_MARKER: &str = "<!-- git-release:end -->";
+
+/// PublishError is returned when the API of a backend rejects a request, or it can't be reached.
+/// The failures are told apart by the status of the responses.
+#[derive(Error, Debug)]
+pub enum PublishError {
+    /// Returned when the release of the tag already exists.
+    #[error("The release already exists, use --force or --upsert to update it")]
+    AlreadyExists,
+
+    /// Returned when the token is not valid, or it doesn't have access to the repository.
+    #[error("Not authorised ({0}), check the token and its permissions on the repository")]
+    Unauthorized(String),
+
+    /// Returned when the repository or the release can't be found.
+    #[error("Not found ({0}), check the remote and that the tag is pushed")]
+    NotFound(String),
+
+    /// Returned when the rate limit of the API is exceeded.
+    #[error("The rate limit of the API is exceeded ({0}), try again later")]
+    RateLimited(String),
+
+    /// Returned when the API rejects the contents of the request.
+    #[error("The request was rejected: {0}")]
+    Validation(String),
+
+    /// Returned when the server can't be reached.
+    #[error("Could not reach the server")]
+    Network(#[source] reqwest::Error),
+
+    /// Returned when the client can't be made, e.g. the url of the proxy is not valid.
+    #[error("Invalid client settings: {0}")]
+    Client(String),
+
+    /// Returned for any other failed response, or a response that can't be read.
+    #[error("Unexpected response from the server: {0}")]
+    Response(String),
+}
+
+impl PublishError {
+    /// Returns the response if it is successful, otherwise the error that describes the failure.
+    ///
+    /// # Errors
+    ///
+    /// If the status of the response is not a success, an `Err` is returned.
+    pub async fn check(response: reqwest::Response) -> Result<reqwest::Response, PublishError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let exhausted = response
+            .headers()
+            .get("x-ratelimit-remaining")
+            .is_some_and(|remaining| remaining == "0");
+        let text = response.text().await.unwrap_or_default();
+        let message = api_message(&text).unwrap_or_else(|| status.to_string());
+        let rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+            || (status == StatusCode::FORBIDDEN
+                && (exhausted || message.to_lowercase().contains("rate limit")));
+        Err(match status {
+            _ if rate_limited => PublishError::RateLimited(message),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => PublishError::Unauthorized(message),
+            StatusCode::NOT_FOUND => PublishError::NotFound(message),
+            StatusCode::CONFLICT => PublishError::AlreadyExists,
+            StatusCode::UNPROCESSABLE_ENTITY if text.contains("already_exists") => {
+                PublishError::AlreadyExists
+            },
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                PublishError::Validation(message)
+            },
+            _ => PublishError::Response(format!("{status}: {message}")),
+        })
+    }
+}
+
+impl From<reqwest::Error> for PublishError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() || err.is_body() {
+            return PublishError::Response(err.to_string());
+        }
+        PublishError::Network(err)
+    }
+}
+
+/// Sends the request and checks its response with `PublishError::check()`.
+///
+/// # Errors
+///
+/// If the server can't be reached or the response is not a success, an `Err` is returned.
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, PublishError> {
+    PublishError::check(request.send().await?).await
+}
+
+/// Returns the message in the body of a failed response. Github, gitlab and gitea all put it in
+/// the `message` field, and github adds the details of the validation errors to the `errors`
+/// field.
+fn api_message(text: &str) -> Option<String> {
+    let body: serde_json::Value = serde_json::from_str(text).ok()?;
+    let message = match body.get("message")? {
+        serde_json::Value::String(message) => message.clone(),
+        message => message.to_string(),
+    };
+    let details: Vec<String> = body
+        .get("errors")
+        .and_then(|errors| errors.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|error| match error {
+            serde_json::Value::String(error) => Some(error.clone()),
+            error => error
+                .get("message")
+                .or_else(|| error.get("code"))
+                .and_then(|m| m.as_str())
+                .map(str::to_owned),
+        })
+        .collect();
+    match details.is_empty() {
+        true => Some(message),
+        false => Some(format!("{message}: {}", details.join(", "))),
+    }
+}
+
+/// A Publisher creates or updates the release of a tag on a backend.
+pub trait Publisher {
+    /// Returns the body of the existing release of the tag, or `None` if there is no release.
+    async fn existing_body(&self) -> Result<Option<String>, PublishError>;
+
+    /// Creates a new release with the body. It returns `PublishError::AlreadyExists` if the
+    /// release already exists.
+    async fn create(&self, body: &str) -> Result<(), PublishError>;
+
+    /// Replaces the contents of the existing release with the body.
+    async fn update(&self, body: &str) -> Result<(), PublishError>;
+}
+
+/// A DraftPublisher can publish the releases that were created as drafts.
+pub trait DraftPublisher: Publisher {
+    /// Publishes the draft release of the tag. It returns false if there is no such draft.
+    async fn publish_draft(&self) -> Result<bool, PublishError>;
+}
+
+/// Outcome is what was done to the release of the tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Created,
+    Updated,
+    PublishedDraft,
+}
+
+/// Mode decides what happens when the release of the tag already exists.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The release is only created, and it fails if it already exists.
+    #[default]
+    Create,
+    /// If the release can't be created, the contents of the existing release are replaced.
+    Force,
+    /// The existing release is updated, keeping the parts of its body that are outside of the
+    /// markers. Otherwise the release is created.
+    Upsert,
+}
+
+/// Returns the notes between the markers.
+pub fn wrap_notes(notes: &str) -> String {
+    format!("{START_MARKER}\n{notes}\n{END_MARKER}")
+}
+
+/// Replaces the generated portion of the existing body with the notes, and keeps the hand-written
+/// text around it. If the body doesn't have the markers, e.g. it was published by an older
+/// version, the whole body is replaced.
+pub fn merge_body(existing: &str, notes: &str) -> String {
+    let notes = wrap_notes(notes);
+    let Some(start) = existing.find(START_MARKER) else {
+        return notes;
+    };
+    let Some(end) = existing[start..].find(END_MARKER) else {
+        return notes;
+    };
+    let end = start + end + END_MARKER.len();
+    format!("{}{notes}{}", &existing[..start], &existing[end..])
+}
+
+/// Publishes the release with the notes, which are put between the markers. What happens to an
+/// existing release is decided by the `mode`.
+///
+/// # Errors
+///
+/// If the release can't be created, and it is not forced or can't be updated, an `Err` is
+/// returned. Only an existing release is force updated; the other failures are returned as they
+/// are.
+pub async fn publish<P: Publisher>(
+    publisher: &P,
+    notes: &str,
+    mode: Mode,
+) -> Result<Outcome, PublishError> {
+    let body = wrap_notes(notes);
+    match mode {
+        Mode::Create => publisher.create(&body).await.map(|_| Outcome::Created),
+        Mode::Force => match publisher.create(&body).await {
+            Ok(()) => Ok(Outcome::Created),
+            Err(PublishError::AlreadyExists) => {
+                publisher.update(&body).await.map(|_| Outcome::Updated)
+            },
+            Err(err) => Err(err),
+        },
+        Mode::Upsert => match publisher.existing_body().await? {
+            Some(existing) => {
+                let body = merge_body(&existing, notes);
+                publisher.update(&body).await.map(|_| Outcome::Updated)
+            },
+            None => publisher.create(&body).await.map(|_| Outcome::Created),
+        },
+    }
+}
@@ -0,0 +1,333 @@
+use super::*;
+
+#[cfg(test)]
+mod backend {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detect() {
+        let tcs = vec![
+            (
+                "git@github.com:arsham/shark.git",
+                None,
+                None,
+                Backend::GitHub,
+            ),
+            (
+                "git@gitlab.com:arsham/shark.git",
+                None,
+                None,
+                Backend::GitLab,
+            ),
+            (
+                "https://gitlab.example.com/a/b",
+                None,
+                None,
+                Backend::GitLab,
+            ),
+            (
+                "git@git.example.com:a/b.git",
+                Some("https://git.example.com"),
+                None,
+                Backend::GitLab,
+            ),
+            ("https://codeberg.org/a/b", None, None, Backend::Gitea),
+            ("git@gitea.example.com:a/b.git", None, None, Backend::Gitea),
+            (
+                "https://git.example.com/a/b",
+                Some("https://gitlab.example.com"),
+                Some("https://git.example.com:3000"),
+                Backend::Gitea,
+            ),
+        ];
+        for (url, gitlab_url, gitea_url, want) in tcs {
+            let remote = RemoteInfo::parse(url).unwrap();
+            let self_hosted = [(Backend::GitLab, gitlab_url), (Backend::Gitea, gitea_url)];
+            assert_eq!(
+                want,
+                Backend::detect(&remote, &self_hosted).unwrap(),
+                "{url}"
+            );
+        }
+    }
+
+    #[test]
+    fn github_enterprise() {
+        let remote = RemoteInfo::parse("git@gitlab.example.com:a/b.git").unwrap();
+        let self_hosted = [(Backend::GitHub, Some("gitlab.example.com"))];
+        assert_eq!(
+            Backend::GitHub,
+            Backend::detect(&remote, &self_hosted).unwrap()
+        );
+
+        // The ssh host of the remote can differ from the host of the server.
+        let remote = RemoteInfo::parse("git@ssh.example.com:a/b.git").unwrap();
+        let self_hosted = [(Backend::GitHub, Some("github.example.com"))];
+        assert_eq!(
+            Backend::GitHub,
+            Backend::detect(&remote, &self_hosted).unwrap()
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        let tcs = [
+            (
+                "git@bitbucket.org:a/b.git",
+                "Publishing is not supported on bitbucket",
+            ),
+            (
+                "https://dev.azure.com/org/project/_git/repo",
+                "Publishing is not supported on azure devops",
+            ),
+            (
+                "https://git.example.com/a/b",
+                "Could not tell where to publish the releases of 'git.example.com'",
+            ),
+        ];
+        for (url, want) in tcs {
+            let remote = RemoteInfo::parse(url).unwrap();
+            let self_hosted = [(Backend::GitHub, None), (Backend::GitLab, None)];
+            let err = Backend::detect(&remote, &self_hosted).unwrap_err();
+            assert!(err.to_string().starts_with(want), "{url}: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod make_latest {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(MakeLatest::True), "true".parse());
+        assert_eq!(Ok(MakeLatest::False), "false".parse());
+        assert_eq!(Ok(MakeLatest::Legacy), "legacy".parse());
+        assert!("yes".parse::<MakeLatest>().is_err());
+    }
+}
+
+#[cfg(test)]
+mod encode_segment {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reserved_characters() {
+        assert_eq!("v1.0.0-rc_1~2", encode_segment("v1.0.0-rc_1~2"));
+        assert_eq!("release%2Fv1.0%2B1", encode_segment("release/v1.0+1"));
+        assert_eq!("a%20b%3F%23", encode_segment("a b?#"));
+    }
+}
+
+#[cfg(test)]
+mod merge_body {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn markers() {
+        let notes = wrap_notes("new");
+        let tcs = vec![
+            ("", notes.clone()),
+            ("hand-written only", notes.clone()),
+            (
+                "<!-- git-release:start -->\nold\n<!-- git-release:end -->",
+                notes.clone(),
+            ),
+            (
+                "Intro\n<!-- git-release:start -->\nold\n<!-- git-release:end -->\nOutro",
+                format!("Intro\n{notes}\nOutro"),
+            ),
+            (
+                "Intro\n<!-- git-release:start -->\nno end marker",
+                notes.clone(),
+            ),
+            (
+                "<!-- git-release:end -->\n<!-- git-release:start -->\nold",
+                notes.clone(),
+            ),
+        ];
+        for (existing, want) in tcs {
+            assert_eq!(want, merge_body(existing, "new"), "{existing}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod modes {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::cell::RefCell;
+
+    /// A backend that keeps the release in memory.
+    #[derive(Default)]
+    struct Memory {
+        body: RefCell<Option<String>>,
+    }
+
+    impl Publisher for Memory {
+        async fn existing_body(&self) -> Result<Option<String>, PublishError> {
+            Ok(self.body.borrow().clone())
+        }
+
+        async fn create(&self, body: &str) -> Result<(), PublishError> {
+            if self.body.borrow().is_some() {
+                return Err(PublishError::AlreadyExists);
+            }
+            *self.body.borrow_mut() = Some(body.to_owned());
+            Ok(())
+        }
+
+        async fn update(&self, body: &str) -> Result<(), PublishError> {
+            *self.body.borrow_mut() = Some(body.to_owned());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn existing_release() -> Result<(), Box<dyn std::error::Error>> {
+        let existing = format!("Intro\n{}", wrap_notes("old"));
+        let memory = Memory {
+            body: RefCell::new(Some(existing.clone())),
+        };
+        assert!(publish(&memory, "new", Mode::Create).await.is_err());
+        assert_eq!(Some(existing), *memory.body.borrow());
+
+        let outcome = publish(&memory, "new", Mode::Upsert).await?;
+        assert_eq!(Outcome::Updated, outcome);
+        assert_eq!(
+            Some(format!("Intro\n{}", wrap_notes("new"))),
+            *memory.body.borrow()
+        );
+
+        let outcome = publish(&memory, "forced", Mode::Force).await?;
+        assert_eq!(Outcome::Updated, outcome);
+        assert_eq!(Some(wrap_notes("forced")), *memory.body.borrow());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn force_only_updates_existing() {
+        struct Unauthorized;
+
+        impl Publisher for Unauthorized {
+            async fn existing_body(&self) -> Result<Option<String>, PublishError> {
+                unreachable!()
+            }
+
+            async fn create(&self, _: &str) -> Result<(), PublishError> {
+                Err(PublishError::Unauthorized("Bad credentials".to_owned()))
+            }
+
+            async fn update(&self, _: &str) -> Result<(), PublishError> {
+                panic!("the release should not be updated")
+            }
+        }
+
+        let res = publish(&Unauthorized, "new", Mode::Force).await;
+        assert!(matches!(res, Err(PublishError::Unauthorized(_))));
+    }
+
+    #[tokio::test]
+    async fn missing_release() -> Result<(), Box<dyn std::error::Error>> {
+        for mode in [Mode::Create, Mode::Force, Mode::Upsert] {
+            let memory = Memory::default();
+            assert_eq!(Outcome::Created, publish(&memory, "new", mode).await?);
+            assert_eq!(Some(wrap_notes("new")), *memory.body.borrow());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod check {
+    use super::*;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn check_response(response: ResponseTemplate) -> Result<(), PublishError> {
+        let server = MockServer::start().await;
+        Mock::given(path("/"))
+            .respond_with(response)
+            .mount(&server)
+            .await;
+        send(reqwest::Client::new().get(server.uri())).await?;
+        Ok(())
+    }
+
+    fn json(status: u16, body: serde_json::Value) -> ResponseTemplate {
+        ResponseTemplate::new(status).set_body_json(body)
+    }
+
+    #[tokio::test]
+    async fn statuses() {
+        let already_exists = json(
+            422,
+            serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [{"resource": "Release", "code": "already_exists", "field": "tag_name"}],
+            }),
+        );
+        let res = check_response(already_exists).await;
+        assert!(matches!(res, Err(PublishError::AlreadyExists)), "{res:?}");
+
+        let res = check_response(ResponseTemplate::new(409)).await;
+        assert!(matches!(res, Err(PublishError::AlreadyExists)), "{res:?}");
+
+        let unauthorized = json(401, serde_json::json!({"message": "Bad credentials"}));
+        let res = check_response(unauthorized).await;
+        assert!(
+            matches!(res, Err(PublishError::Unauthorized(ref m)) if m == "Bad credentials"),
+            "{res:?}"
+        );
+
+        let res = check_response(ResponseTemplate::new(404)).await;
+        assert!(matches!(res, Err(PublishError::NotFound(_))), "{res:?}");
+
+        let rate_limited = json(
+            403,
+            serde_json::json!({"message": "API rate limit exceeded"}),
+        )
+        .insert_header("x-ratelimit-remaining", "0");
+        let res = check_response(rate_limited).await;
+        assert!(matches!(res, Err(PublishError::RateLimited(_))), "{res:?}");
+
+        let res = check_response(ResponseTemplate::new(429)).await;
+        assert!(matches!(res, Err(PublishError::RateLimited(_))), "{res:?}");
+
+        let forbidden = json(
+            403,
+            serde_json::json!({"message": "Must have admin rights"}),
+        );
+        let res = check_response(forbidden).await;
+        assert!(matches!(res, Err(PublishError::Unauthorized(_))), "{res:?}");
+
+        let invalid = json(
+            422,
+            serde_json::json!({
+                "message": "Validation Failed",
+                "errors": [{"code": "custom", "message": "name is too long"}],
+            }),
+        );
+        let res = check_response(invalid).await;
+        assert!(
+            matches!(res, Err(PublishError::Validation(ref m)) if m == "Validation Failed: name is too long"),
+            "{res:?}"
+        );
+
+        let res = check_response(ResponseTemplate::new(500)).await;
+        assert!(matches!(res, Err(PublishError::Response(_))), "{res:?}");
+
+        assert!(check_response(ResponseTemplate::new(204)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn network() {
+        // Nothing listens on the port 1.
+        let res = send(reqwest::Client::new().get("http://127.0.0.1:1")).await;
+        assert!(matches!(res, Err(PublishError::Network(_))), "{res:?}");
+    }
+}
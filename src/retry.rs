@@ -0,0 +1,158 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+
+use crate::publish::PublishError;
+
+#[cfg(test)]
+#[path = "./retry_test.rs"]
+mod retry_test;
+
+/// Retry is the budget for retrying the requests that fail because of the rate limits, server
+/// errors or network failures. The delays grow exponentially from `base_delay`, unless the server
+/// asks for a delay with the `Retry-After` or `x-ratelimit-reset` headers. The server might have
+/// applied a request before it failed, so only the requests that can be repeated safely are
+/// retried on server and network errors. The others are only retried when they are rate limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// The number of retries after the first attempt.
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    /// Requests are not retried if the server asks for a longer delay.
+    pub max_delay: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            max_retries: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Retry {
+    /// Sends the request with `execute`, and retries it while the budget allows. The final
+    /// response is checked with `PublishError::check()`. Requests with bodies that can't be
+    /// cloned are not retried.
+    ///
+    /// # Errors
+    ///
+    /// If the last attempt fails, its error is returned.
+    pub async fn send<F>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+        execute: F,
+    ) -> Result<reqwest::Response, PublishError>
+    where
+        F: AsyncFn(reqwest::RequestBuilder) -> Result<reqwest::Response, PublishError>,
+    {
+        let repeatable = request
+            .try_clone()
+            .and_then(|request| request.build().ok())
+            .is_some_and(|request| repeatable(request.method()));
+        let mut attempt = 0;
+        loop {
+            let next = request.try_clone();
+            let result = execute(request).await;
+            let delay = match result {
+                Ok(ref response) => {
+                    self.delay(response.status(), response.headers(), attempt, repeatable)
+                },
+                Err(PublishError::Network(_)) if repeatable => Some(self.backoff(attempt)),
+                Err(_) => None,
+            };
+            match (next, delay) {
+                (Some(next), Some(delay)) if attempt < self.max_retries => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                    request = next;
+                },
+                _ => return PublishError::check(result?).await,
+            }
+        }
+    }
+
+    /// Returns how long to wait before retrying the response, or `None` if it should not be
+    /// retried. Server errors are only retried if the request is `repeatable`.
+    fn delay(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        attempt: u32,
+        repeatable: bool,
+    ) -> Option<Duration> {
+        let requested = retry_after(headers).or_else(|| rate_limit_reset(headers));
+        let retryable = status == StatusCode::TOO_MANY_REQUESTS
+            || (repeatable && status.is_server_error())
+            || (status == StatusCode::FORBIDDEN && (requested.is_some() || exhausted(headers)));
+        if !retryable {
+            return None;
+        }
+        match requested {
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// Returns the exponential delay of the attempt, capped at the `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2_u32.saturating_pow(attempt))
+            .min(self.max_delay)
+    }
+}
+
+/// Returns true if sending the request again has the same effect as sending it once. The updates
+/// with `PATCH` replace the contents of the releases, so they can be repeated. A `POST` that is
+/// repeated could create a duplicate release or asset.
+fn repeatable(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+/// Returns true if the rate limit has no remaining requests.
+fn exhausted(headers: &HeaderMap) -> bool {
+    headers
+        .get("x-ratelimit-remaining")
+        .is_some_and(|remaining| remaining == "0")
+}
+
+/// Returns the delay of the `Retry-After` header, which is either in seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let date = u64::try_from(date.timestamp()).ok()?;
+    Some(until(date))
+}
+
+/// Returns the time until the rate limit is reset, if the limit is exhausted. The
+/// `x-ratelimit-reset` header is the time of the reset in seconds since the epoch.
+fn rate_limit_reset(headers: &HeaderMap) -> Option<Duration> {
+    if !exhausted(headers) {
+        return None;
+    }
+    let reset = headers
+        .get("x-ratelimit-reset")?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()?;
+    Some(until(reset))
+}
+
+/// Returns the duration until the given seconds since the epoch, or zero if it has passed.
+fn until(epoch_seconds: u64) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Duration::from_secs(epoch_seconds).saturating_sub(now)
+}
@@ -0,0 +1,232 @@
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::*;
+
+fn retry() -> Retry {
+    Retry {
+        max_retries: 2,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_secs(5),
+    }
+}
+
+/// Mounts a mock that responds with the `first` response for the given number of times, and with
+/// 200 afterwards.
+async fn mock_server(first: ResponseTemplate, times: u64) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/releases"))
+        .respond_with(first)
+        .up_to_n_times(times)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/releases"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn send(retry: Retry, server: &MockServer) -> Result<reqwest::Response, PublishError> {
+    let request = reqwest::Client::new().get(format!("{}/releases", server.uri()));
+    retry
+        .send(request, async |request| Ok(request.send().await?))
+        .await
+}
+
+async fn requests(server: &MockServer) -> usize {
+    server.received_requests().await.unwrap_or_default().len()
+}
+
+#[cfg(test)]
+mod send {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn retry_after() -> Result<(), Box<dyn std::error::Error>> {
+        let limited = ResponseTemplate::new(429).insert_header("Retry-After", "0");
+        let server = mock_server(limited, 1).await;
+        assert_eq!(StatusCode::OK, send(retry(), &server).await?.status());
+        assert_eq!(2, requests(&server).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn secondary_rate_limit() -> Result<(), Box<dyn std::error::Error>> {
+        let limited = ResponseTemplate::new(403)
+            .insert_header("Retry-After", "0")
+            .set_body_json(serde_json::json!({
+                "message": "You have exceeded a secondary rate limit",
+            }));
+        let server = mock_server(limited, 2).await;
+        assert_eq!(StatusCode::OK, send(retry(), &server).await?.status());
+        assert_eq!(3, requests(&server).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn server_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let server = mock_server(ResponseTemplate::new(502), 2).await;
+        assert_eq!(StatusCode::OK, send(retry(), &server).await?.status());
+        assert_eq!(3, requests(&server).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn budget_exhausted() {
+        let limited = ResponseTemplate::new(429).insert_header("Retry-After", "0");
+        let server = mock_server(limited, 10).await;
+        let res = send(retry(), &server).await;
+        assert!(matches!(res, Err(PublishError::RateLimited(_))), "{res:?}");
+        assert_eq!(3, requests(&server).await);
+
+        let server = mock_server(ResponseTemplate::new(503), 10).await;
+        let no_retries = Retry {
+            max_retries: 0,
+            ..retry()
+        };
+        assert!(send(no_retries, &server).await.is_err());
+        assert_eq!(1, requests(&server).await);
+    }
+
+    #[tokio::test]
+    async fn reset_too_far() {
+        let reset =
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap() + Duration::from_secs(3600);
+        let limited = ResponseTemplate::new(403)
+            .insert_header("x-ratelimit-remaining", "0")
+            .insert_header("x-ratelimit-reset", reset.as_secs().to_string().as_str());
+        let server = mock_server(limited, 10).await;
+        let res = send(retry(), &server).await;
+        assert!(matches!(res, Err(PublishError::RateLimited(_))), "{res:?}");
+        assert_eq!(1, requests(&server).await);
+    }
+
+    #[tokio::test]
+    async fn post_server_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/releases"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        let request = reqwest::Client::new().post(format!("{}/releases", server.uri()));
+        let res = retry()
+            .send(request, async |request| Ok(request.send().await?))
+            .await;
+        assert!(matches!(res, Err(PublishError::Response(_))), "{res:?}");
+        assert_eq!(1, requests(&server).await);
+    }
+
+    #[tokio::test]
+    async fn post_rate_limited() -> Result<(), Box<dyn std::error::Error>> {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/releases"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/releases"))
+            .respond_with(ResponseTemplate::new(201))
+            .mount(&server)
+            .await;
+        let request = reqwest::Client::new().post(format!("{}/releases", server.uri()));
+        let res = retry()
+            .send(request, async |request| Ok(request.send().await?))
+            .await?;
+        assert_eq!(StatusCode::CREATED, res.status());
+        assert_eq!(2, requests(&server).await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn not_retryable() {
+        for status in [401, 403, 404, 422] {
+            let server = mock_server(ResponseTemplate::new(status), 10).await;
+            assert!(send(retry(), &server).await.is_err(), "{status}");
+            assert_eq!(1, requests(&server).await, "{status}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod delay {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for &(name, value) in pairs {
+            headers.insert(name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn backoff() {
+        let retry = Retry {
+            max_retries: 10,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+        };
+        let delays: Vec<_> = (0..6).map(|i| retry.backoff(i).as_secs()).collect();
+        assert_eq!(vec![1, 2, 4, 8, 10, 10], delays);
+    }
+
+    #[test]
+    fn headers_and_statuses() {
+        let retry = Retry::default();
+        let empty = HeaderMap::new();
+        assert_eq!(
+            Some(Duration::from_secs(4)),
+            retry.delay(StatusCode::SERVICE_UNAVAILABLE, &empty, 2, true)
+        );
+        assert_eq!(None, retry.delay(StatusCode::FORBIDDEN, &empty, 0, true));
+        assert_eq!(None, retry.delay(StatusCode::NOT_FOUND, &empty, 0, true));
+
+        let after = headers(&[("retry-after", "7")]);
+        assert_eq!(
+            Some(Duration::from_secs(7)),
+            retry.delay(StatusCode::TOO_MANY_REQUESTS, &after, 0, true)
+        );
+        assert_eq!(
+            Some(Duration::from_secs(7)),
+            retry.delay(StatusCode::FORBIDDEN, &after, 0, true)
+        );
+        let too_long = headers(&[("retry-after", "120")]);
+        assert_eq!(
+            None,
+            retry.delay(StatusCode::TOO_MANY_REQUESTS, &too_long, 0, true)
+        );
+
+        let past = headers(&[("retry-after", "Wed, 21 Oct 2015 07:28:00 GMT")]);
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry.delay(StatusCode::TOO_MANY_REQUESTS, &past, 0, true)
+        );
+
+        let reset = headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "0")]);
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry.delay(StatusCode::FORBIDDEN, &reset, 0, true)
+        );
+        assert_eq!(None, retry.delay(StatusCode::BAD_GATEWAY, &empty, 0, false));
+        assert_eq!(
+            Some(Duration::ZERO),
+            retry.delay(StatusCode::FORBIDDEN, &reset, 0, false)
+        );
+        let remaining = headers(&[("x-ratelimit-remaining", "10"), ("x-ratelimit-reset", "0")]);
+        assert_eq!(
+            None,
+            retry.delay(StatusCode::FORBIDDEN, &remaining, 0, true)
+        );
+    }
+}
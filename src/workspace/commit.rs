@@ -1,7 +1,13 @@
 use std::fmt::Display;
+use std::rc::Rc;
 
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::Deserialize;
+
+use super::pull_request::PullRequest;
+use super::remote::RemoteInfo;
+use crate::config::{Config, Merges};
 
 #[cfg(test)]
 #[path = "./commit_test.rs"]
@@ -11,71 +17,453 @@ lazy_static! {
     static ref SUMMARY_RE: Regex =
         Regex::new(r#"^\s*(\w+)( *\(([ /.\w,_-]+)?\) *)?(!)? *:?(.*)"#).unwrap();
     static ref REF_RE: Regex = Regex::new(r#"\(?\w+\s+#(\d+)\)?"#).unwrap();
+    static ref BRACKETS_RE: Regex = Regex::new(r#"\(([^()]*)\)|\[([^\[\]]*)\]"#).unwrap();
+    static ref SPACES_RE: Regex = Regex::new(r#"\s{2,}"#).unwrap();
+    static ref REVERT_RE: Regex = Regex::new(r#"^Revert "(.+)"\s*$"#).unwrap();
+    static ref REVERTS_RE: Regex =
+        Regex::new(r#"(?m)^This reverts commit ([0-9a-f]{7,40})"#).unwrap();
+    static ref MERGE_RE: Regex = Regex::new(r#"^Merge pull request #(\d+) from "#).unwrap();
+    static ref MERGE_REQUEST_RE: Regex =
+        Regex::new(r#"(?m)^See merge request \S*!(\d+)\s*$"#).unwrap();
+    static ref BULLET_RE: Regex = Regex::new(r#"^\s*[*-]\s+(\S.*)$"#).unwrap();
+    static ref CHERRY_PICK_RE: Regex =
+        Regex::new(r#"\(cherry picked from commit ([0-9a-f]{7,40})\)"#).unwrap();
 }
 
 /// A Commit represents a commit in the repository with its metadata.
 #[derive(Debug, Clone)]
 pub struct Commit<'a> {
     commit: git2::Commit<'a>,
+    config: Rc<Config>,
+    pull_request: Option<PullRequest>,
+    /// Set for the merge commits when the merge policy is set to pull requests.
+    merge: Option<Merge>,
+    /// A line of a squash merged commit body, that this commit is listed with.
+    entry: Option<String>,
+    /// Set when a cherry-pick of this commit, or its original, is in the same release.
+    backported: bool,
+}
+
+impl<'a> Commit<'a> {
+    /// Creates a new Commit that uses the given config for extracting its metadata. If the merge
+    /// policy is set to pull requests and this is a merge commit, it is listed as the pull request
+    /// or the branch that it merged.
+    pub fn new(commit: git2::Commit<'a>, config: Rc<Config>) -> Self {
+        let merge = match config.merges {
+            Merges::PullRequests => merged_branch(&commit),
+            _ => None,
+        };
+        Commit {
+            commit,
+            config,
+            pull_request: None,
+            merge,
+            entry: None,
+            backported: false,
+        }
+    }
+
+    /// Returns one commit for each of the bullet lines in the body that start with a verb, e.g.
+    /// `* feat: something`, as github creates them when squash merging a pull request. If there
+    /// are no such lines, the commit itself is returned.
+    pub fn expand(self) -> Vec<Commit<'a>> {
+        let entries: Vec<String> = self
+            .commit
+            .body()
+            .unwrap_or("")
+            .lines()
+            .filter_map(|line| Some(BULLET_RE.captures(line)?.get(1)?.as_str().trim()))
+            .filter(|entry| summary_verb(entry) != Verb::Misc)
+            .map(str::to_owned)
+            .collect();
+        if entries.is_empty() {
+            return vec![self];
+        }
+        entries
+            .into_iter()
+            .map(|entry| Commit {
+                entry: Some(entry),
+                ..self.clone()
+            })
+            .collect()
+    }
+}
+
+/// Returns the verb at the start of the summary, or `Verb::Misc` if there is none.
+fn summary_verb(summary: &str) -> Verb {
+    SUMMARY_RE
+        .captures(summary)
+        .and_then(|caps| {
+            caps.get(1)
+                .map(|verb| verb.as_str().to_lowercase().as_str().into())
+        })
+        .unwrap_or(Verb::Misc)
+}
+
+/// Merge is a branch that was merged with a merge commit, e.g. the branch of a pull request.
+#[derive(Debug, Clone)]
+struct Merge {
+    /// The title of the pull request or the merge request, which github and gitlab put in the
+    /// body of the merge commit.
+    title: Option<String>,
+    /// The pull request on github, or the merge request on gitlab.
+    reference: Option<Reference>,
+    /// The summaries of the commits of the branch, from the oldest to the newest.
+    branch: Vec<String>,
+}
+
+impl Merge {
+    /// Returns the title of the merge. Without a title, e.g. for the plain `git merge` commits,
+    /// the summary of the first commit of the branch with a verb is used.
+    fn title(&self) -> Option<&str> {
+        self.title.as_deref().or_else(|| {
+            self.branch
+                .iter()
+                .find(|summary| summary_verb(summary) != Verb::Misc)
+                .or_else(|| self.branch.first())
+                .map(String::as_str)
+        })
+    }
+
+    /// Returns the verb of the first commit of the branch that has one.
+    fn branch_verb(&self) -> Option<Verb> {
+        self.branch
+            .iter()
+            .map(|summary| summary_verb(summary))
+            .find(|verb| *verb != Verb::Misc)
+    }
+}
+
+/// Returns the merge of a merge commit. The pull requests of github are recognised by the summary,
+/// and the merge requests of gitlab by the `See merge request` line of the body. Both have the
+/// title in the first line of the body.
+fn merged_branch(commit: &git2::Commit) -> Option<Merge> {
+    if commit.parent_count() < 2 {
+        return None;
+    }
+    let body = commit.body().unwrap_or("");
+    let number = |re: &Regex, text: &str| re.captures(text)?.get(1)?.as_str().parse().ok();
+    let reference = match number(&MERGE_RE, commit.summary().unwrap_or("")) {
+        Some(number) => Some(Reference::Issue(number)),
+        None => number(&MERGE_REQUEST_RE, body).map(Reference::MergeRequest),
+    };
+    let title = reference
+        .as_ref()
+        .and_then(|_| body.lines().map(str::trim).find(|line| !line.is_empty()))
+        .filter(|line| !MERGE_REQUEST_RE.is_match(line))
+        .map(str::to_owned);
+    Some(Merge {
+        title,
+        reference,
+        branch: vec![],
+    })
 }
 
 impl Commit<'_> {
-    /// Returns the summary of the commit. If there is an error or the body is not valid UTF-8 it
-    /// returns an empty string.
+    /// Returns the id of the commit.
+    pub fn id(&self) -> git2::Oid {
+        self.commit.id()
+    }
+
+    /// Returns the abbreviated id of the commit.
+    pub fn short_id(&self) -> String {
+        let mut id = self.commit.id().to_string();
+        id.truncate(7);
+        id
+    }
+
+    /// Sets the pull request this commit was merged with. After this the title, verb and
+    /// subjects are taken from the pull request's title, and its number is added to the
+    /// references.
+    pub fn set_pull_request(&mut self, pull_request: PullRequest) {
+        self.pull_request = Some(pull_request);
+    }
+
+    /// Sets the commits of the branch that this merge commit merged, from the oldest to the
+    /// newest. They give the title of the merges without one, and the verb of the merges whose
+    /// titles don't have a verb.
+    pub fn set_branch(&mut self, commits: &[git2::Commit]) {
+        if let Some(ref mut merge) = self.merge {
+            merge.branch = commits
+                .iter()
+                .filter_map(|commit| commit.summary().map(str::to_owned))
+                .collect();
+        }
+    }
+
+    /// Marks the commit as back-ported. If configured, this is noted in the release.
+    pub fn set_backported(&mut self) {
+        self.backported = true;
+    }
+
+    /// Returns the id, or the prefix of the id, of the commit that this commit was cherry-picked
+    /// from. This is only set when the commit was cherry-picked with the `-x` flag.
+    pub fn cherry_picked_from(&self) -> Option<&str> {
+        CHERRY_PICK_RE
+            .captures(self.commit.message()?)
+            .and_then(|caps| Some(caps.get(1)?.as_str()))
+    }
+
+    /// Returns true if this commit and the other one are cherry-picks of each other, or of the
+    /// same commit.
+    pub fn is_cherry_pick_of(&self, other: &Commit) -> bool {
+        let picked = |commit: &Commit, id: &str| commit.id().to_string().starts_with(id);
+        match (self.cherry_picked_from(), other.cherry_picked_from()) {
+            (Some(a), Some(b)) => a.starts_with(b) || b.starts_with(a),
+            (Some(id), None) => picked(other, id),
+            (None, Some(id)) => picked(self, id),
+            (None, None) => false,
+        }
+    }
+
+    /// Returns the labels of the pull request of the commit, if any.
+    pub fn labels(&self) -> &[String] {
+        match self.pull_request {
+            Some(ref pr) => &pr.labels,
+            None => &[],
+        }
+    }
+
+    /// Returns the github login of the author of the pull request of the commit, if any.
+    pub fn author(&self) -> Option<&str> {
+        self.pull_request.as_ref().map(|pr| pr.author.as_str())
+    }
+
+    /// Returns the title of the pull request if set, otherwise the summary of the commit.
+    /// For reverts, the summary of the reverted commit is returned, for the merges the title of
+    /// the merge, and for the expanded commits their line in the body.
+    fn summary(&self) -> Option<&str> {
+        if let Some(ref entry) = self.entry {
+            return Some(entry);
+        }
+        if let Some(ref pr) = self.pull_request {
+            return Some(pr.title.as_str());
+        }
+        self.merge
+            .as_ref()
+            .and_then(Merge::title)
+            .or_else(|| self.reverted_summary())
+            .or_else(|| self.commit.summary())
+    }
+
+    /// Returns the summary of the reverted commit if the summary is in git's revert format, e.g.
+    /// `Revert "feat: something"`.
+    fn reverted_summary(&self) -> Option<&str> {
+        REVERT_RE
+            .captures(self.commit.summary()?)
+            .and_then(|caps| Some(caps.get(1)?.as_str()))
+    }
+
+    /// Returns the id of the reverted commit from the `This reverts commit <id>` line of the body.
+    /// The id can be abbreviated.
+    pub fn reverted_id(&self) -> Option<&str> {
+        REVERTS_RE
+            .captures(self.commit.body()?)
+            .and_then(|caps| Some(caps.get(1)?.as_str()))
+    }
+
+    /// Returns true if this commit reverts another commit.
+    pub fn is_revert(&self) -> bool {
+        self.reverted_id().is_some() || self.reverted_summary().is_some()
+    }
+
+    /// Returns true if this commit reverts the other commit. The id of the reverted commit is
+    /// preferred, and the summary is only compared when there is no id in the body.
+    pub fn reverts(&self, other: &Commit) -> bool {
+        match self.reverted_id() {
+            Some(id) => other.id().to_string().starts_with(id),
+            None => self
+                .reverted_summary()
+                .is_some_and(|summary| Some(summary) == other.commit.summary()),
+        }
+    }
+
+    /// Returns the summary of the commit, without the references to the issues. If there is an
+    /// error or the body is not valid UTF-8 it returns `None`.
     pub fn title(&self) -> Option<String> {
-        self.commit
-            .summary()
-            .map(|summary| REF_RE.replace_all(summary, "").trim().to_owned())
+        self.summary().map(|summary| {
+            let title = REF_RE.replace_all(summary, "");
+            if self.config.trackers.is_empty() {
+                return title.trim().to_owned();
+            }
+            let strip_keys = |text: &str| {
+                self.config
+                    .trackers
+                    .iter()
+                    .fold(text.to_owned(), |text, tracker| {
+                        tracker.pattern.replace_all(&text, "").into_owned()
+                    })
+            };
+            // Brackets are only removed with the keys they hold, e.g. `[PROJ-1]`, and the other
+            // brackets of the summary are kept.
+            let title = BRACKETS_RE.replace_all(&title, |caps: &regex::Captures| {
+                let inner = caps
+                    .get(1)
+                    .or_else(|| caps.get(2))
+                    .map_or("", |m| m.as_str());
+                let rest = strip_keys(inner);
+                let only_keys = rest != inner
+                    && rest
+                        .trim_matches(|c: char| c.is_whitespace() || c == ',')
+                        .is_empty();
+                match only_keys {
+                    true => String::new(),
+                    false => caps[0].to_owned(),
+                }
+            });
+            let title = strip_keys(&title);
+            SPACES_RE.replace_all(&title, " ").trim().to_owned()
+        })
     }
 
-    /// Returns the verb in the summary of the commit message if specified.
+    /// Returns the verb in the summary of the commit message if specified. If the pull request of
+    /// the commit has any of the labels in the config, the verb of the first one is returned.
+    /// Reverts are always in their own group, and expanded commits use the verb of their line.
     pub fn verb(&self) -> Verb {
-        return self
-            .commit
-            .summary()
-            .and_then(|title| {
-                SUMMARY_RE.captures(title).and_then(|caps| {
-                    caps.get(1)
-                        .map(|verb| verb.as_str().to_lowercase().as_str().into())
-                })
-            })
-            .unwrap_or(Verb::Misc);
+        if let Some(ref entry) = self.entry {
+            return summary_verb(entry);
+        }
+        if self.pull_request.is_none() && self.is_revert() {
+            return Verb::Reverts;
+        }
+        if let Some(ref pr) = self.pull_request {
+            let verb = pr
+                .labels
+                .iter()
+                .find_map(|label| self.config.labels.get(label));
+            if let Some(verb) = verb {
+                return verb.clone();
+            }
+        }
+        match self.summary().map(summary_verb).unwrap_or(Verb::Misc) {
+            Verb::Misc => self
+                .merge
+                .as_ref()
+                .and_then(Merge::branch_verb)
+                .unwrap_or(Verb::Misc),
+            verb => verb,
+        }
     }
 
-    /// Returns a vector of references to other issues on github.
+    /// Returns a vector of references to other issues on github, followed by the keys of the
+    /// configured issue trackers. If the commit has a pull request, its number comes first. Each
+    /// reference is only returned once.
     pub fn references(&self) -> Vec<Reference> {
-        let body = &format!(
-            "{}\n{}",
-            self.commit.summary().unwrap_or(""),
-            self.commit.body().unwrap_or("")
-        );
+        // The body of an expanded commit belongs to all of its lines.
+        let body = match self.entry {
+            Some(_) => self.commit.summary(),
+            None => self.commit.body(),
+        };
+        let body = &format!("{}\n{}", self.summary().unwrap_or(""), body.unwrap_or(""));
         let mut refs = vec![];
+        if let Some(ref pr) = self.pull_request {
+            refs.push(Reference::Issue(pr.number));
+        }
+        if let Some(reference) = self.merge.as_ref().and_then(|m| m.reference.clone()) {
+            if !refs.contains(&reference) {
+                refs.push(reference);
+            }
+        }
         for cap in REF_RE.captures_iter(body) {
             if let Some(num) = cap.get(1) {
                 if let Ok(num) = num.as_str().parse() {
-                    refs.push(Reference(num));
+                    let reference = Reference::Issue(num);
+                    if !refs.contains(&reference) {
+                        refs.push(reference);
+                    }
+                }
+            }
+        }
+        for tracker in &self.config.trackers {
+            for key in tracker.pattern.find_iter(body) {
+                let reference = Reference::Tracker {
+                    key: key.as_str().to_owned(),
+                    url: tracker.link(key.as_str()),
+                };
+                if !refs.contains(&reference) {
+                    refs.push(reference);
                 }
             }
         }
         refs
     }
 
-    /// Returns a vector of subjects in the title if provided. Subjects are separated by comma.
+    /// Returns a vector of subjects in the title if provided. Subjects are separated by comma,
+    /// and are normalised with the scopes config. Duplicates and empty subjects are removed, and
+    /// if none are left it returns `None`.
     // TODO: rename this to avoid confusion with git's subject.
-    pub fn subjects(&self) -> Option<Vec<&str>> {
-        self.commit.summary().and_then(|title| {
-            return SUMMARY_RE
+    pub fn subjects(&self) -> Option<Vec<String>> {
+        let raw = self.summary().and_then(|title| {
+            SUMMARY_RE
                 .captures(title)
-                .and_then(|caps| Some(caps.get(3)?.as_str().split(',').map(str::trim).collect()));
+                .and_then(|caps| Some(caps.get(3)?.as_str().split(',').map(str::trim)))
+        })?;
+        let mut subjects: Vec<String> = vec![];
+        let raw = raw.filter(|s| !s.is_empty());
+        for subject in raw.map(|s| self.config.scopes.normalise(s)) {
+            if !subjects.contains(&subject) {
+                subjects.push(subject);
+            }
+        }
+        (!subjects.is_empty()).then_some(subjects)
+    }
+
+    /// Returns the subjects that are not in the allowed list of scopes. If there is no allowed
+    /// list, it returns an empty vector.
+    pub fn unknown_subjects(&self) -> Vec<String> {
+        self.subjects()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|s| !self.config.scopes.is_allowed(s))
+            .collect()
+    }
+
+    /// Returns the trailers of the commit message, e.g. `Signed-off-by: name`.
+    pub fn trailers(&self) -> Vec<(String, String)> {
+        self.commit
+            .message()
+            .and_then(|message| git2::message_trailers_strs(message).ok())
+            .map(|trailers| {
+                trailers
+                    .iter()
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the commit matches any of the ignore rules in the config.
+    pub fn is_ignored(&self) -> bool {
+        if self.config.ignore.is_empty() {
+            return false;
+        }
+        let author = self.commit.author();
+        let name = author.name().unwrap_or("");
+        let email = author.email().unwrap_or("");
+        // The expanded commits are matched by their own lines.
+        let summary = self.summary().unwrap_or("");
+        let subjects = self.subjects().unwrap_or_default();
+        let trailers = self.trailers();
+
+        self.config.ignore.iter().any(|rule| {
+            rule.verb.as_ref().is_none_or(|verb| *verb == self.verb())
+                && rule.scope.as_ref().is_none_or(|scope| {
+                    let scope = self.config.scopes.normalise(scope);
+                    subjects.contains(&scope)
+                })
+                && rule.author.as_ref().is_none_or(|re| re.is_match(name))
+                && rule.email.as_ref().is_none_or(|re| re.is_match(email))
+                && rule.summary.as_ref().is_none_or(|re| re.is_match(summary))
+                && (rule.trailer.is_none()
+                    || trailers.iter().any(|(k, v)| rule.matches_trailer(k, v)))
         })
     }
 
     /// Returns true if the commit has breaking changes. There are two ways a commit is breaking:
     /// 1. If the title has an explanation mark in front of the verb.
-    /// 2. If the footer starts with `BREAKING CHANGE:`.
+    /// 2. If the footer starts with `BREAKING CHANGE:`. This is not checked for expanded commits.
     pub fn is_breaking(&self) -> bool {
-        self.commit
-            .summary()
+        self.summary()
             .and_then(|title| {
                 SUMMARY_RE
                     .captures(title)
@@ -84,6 +472,7 @@ impl Commit<'_> {
             .unwrap_or_else(|| {
                 self.commit
                     .body()
+                    .filter(|_| self.entry.is_none())
                     .map(|body| {
                         body.lines()
                             .last()
@@ -97,7 +486,7 @@ impl Commit<'_> {
 
 impl<'a> From<git2::Commit<'a>> for Commit<'a> {
     fn from(commit: git2::Commit<'a>) -> Self {
-        Commit { commit }
+        Commit::new(commit, Rc::default())
     }
 }
 
@@ -107,18 +496,36 @@ impl<'a> PartialEq for Commit<'a> {
     }
 }
 
-/// A Reference represents a link to a github issue.
-#[derive(Debug, PartialEq, Eq)]
-pub struct Reference(pub u16);
+/// A Reference represents a link to a github issue, a gitlab merge request, or to an issue in one
+/// of the configured trackers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reference {
+    Issue(u64),
+    MergeRequest(u64),
+    Tracker { key: String, url: String },
+}
 
 impl Reference {
-    /// Returns a pound sign with the issue number.
-    pub fn issue_ref(&self) -> String {
-        format!("#{}", self.0)
+    /// Returns a pound sign with the issue number for github issues, an exclamation mark with the
+    /// number for gitlab merge requests, and a markdown link to the tracker for the others. If the
+    /// remote is given, the issues and merge requests are linked to it.
+    pub fn issue_ref(&self, remote: Option<&RemoteInfo>) -> String {
+        match (self, remote) {
+            (Reference::Issue(num), Some(remote)) => {
+                format!("[#{num}]({})", remote.issue_url(*num))
+            },
+            (Reference::Issue(num), None) => format!("#{num}"),
+            (Reference::MergeRequest(num), Some(remote)) => {
+                format!("[!{num}]({})", remote.merge_request_url(*num))
+            },
+            (Reference::MergeRequest(num), None) => format!("!{num}"),
+            (Reference::Tracker { key, url }, _) => format!("[{key}]({url})"),
+        }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(from = "String")]
 pub enum Verb {
     Feature,
     Fix,
@@ -128,6 +535,7 @@ pub enum Verb {
     Style,
     CI,
     Documentation,
+    Reverts,
     Misc,
 }
 
@@ -145,15 +553,28 @@ impl From<&str> for Verb {
             "style" => Verb::Style,
             "ci" => Verb::CI,
             "doc" | "docs" => Verb::Documentation,
+            "revert" | "reverts" => Verb::Reverts,
             _ => Verb::Misc,
         }
     }
 }
 
-impl Display for Commit<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl From<String> for Verb {
+    fn from(value: String) -> Self {
+        value.to_lowercase().as_str().into()
+    }
+}
+
+impl<'a> Commit<'a> {
+    /// Returns a displayable entry of the commit that doesn't have the subjects in front of the
+    /// title. This is useful when the commit is already listed under its subject.
+    pub fn without_subjects(&self) -> WithoutSubjects<'_, 'a> {
+        WithoutSubjects(self)
+    }
+
+    fn fmt_entry(&self, f: &mut std::fmt::Formatter<'_>, with_subjects: bool) -> std::fmt::Result {
         let mut subjects = String::new();
-        if let Some(s) = self.subjects() {
+        if let Some(s) = self.subjects().filter(|_| with_subjects) {
             subjects.push_str("**");
             subjects.push_str(&s.join(", "));
             subjects.push_str(":** ");
@@ -180,22 +601,48 @@ impl Display for Commit<'_> {
             ""
         };
 
+        let remote = self.config.links();
         let mut refs = self
             .references()
             .into_iter()
-            .map(|reference| format!("ref {}", reference.issue_ref()))
-            .collect::<Vec<String>>()
-            .join(", ");
+            .map(|reference| format!("ref {}", reference.issue_ref(remote)))
+            .collect::<Vec<String>>();
+        if let Some(remote) = remote {
+            let id = self.id().to_string();
+            refs.push(format!("[{}]({})", self.short_id(), remote.commit_url(&id)));
+        }
+        let mut refs = refs.join(", ");
         if !refs.is_empty() {
             refs.insert_str(0, " (");
             refs.push(')');
         }
 
-        write!(f, "{subjects}{title}{breaking}{refs}")?;
+        let backported = if self.backported && self.config.note_backports {
+            " [back-ported]"
+        } else {
+            ""
+        };
+
+        write!(f, "{subjects}{title}{breaking}{refs}{backported}")?;
         Ok(())
     }
 }
 
+impl Display for Commit<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_entry(f, true)
+    }
+}
+
+/// WithoutSubjects displays a commit without its subjects.
+pub struct WithoutSubjects<'c, 'a>(&'c Commit<'a>);
+
+impl Display for WithoutSubjects<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt_entry(f, false)
+    }
+}
+
 fn first_letter_uppercase(s: &mut String) {
     let mut c = s.chars();
     if let Some(f) = c.next() {
@@ -1,7 +1,18 @@
+use std::rc::Rc;
+
 use crate::common_test::new_commit;
+use crate::config::Config;
 use crate::workspace::commit::Reference;
 use crate::workspace::commit::{Commit, Verb};
 
+const TRACKERS: &str = r#"
+trackers:
+  - pattern: 'PROJ-\d+'
+    url: https://jira.example.com/browse/{key}
+  - pattern: 'ENG-\d+'
+    url: https://linear.app/team/issue/{key}
+"#;
+
 #[cfg(test)]
 mod title {
     use super::*;
@@ -17,6 +28,23 @@ mod title {
         assert_eq!(title, res);
         Ok(())
     }
+
+    #[test]
+    fn tracker_brackets() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(TRACKERS.parse()?);
+        let tcs = vec![
+            ("call init() on startup PROJ-1", "call init() on startup"),
+            ("handle empty [] input [ENG-2]", "handle empty [] input"),
+            ("fix this (PROJ-1, ENG-2) now", "fix this now"),
+            ("keep (notes) and [tags]", "keep (notes) and [tags]"),
+        ];
+        for (summary, want) in tcs {
+            let (repo, oid) = new_commit("filename", summary)?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, commit.title().ok_or("no title")?);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -66,6 +94,7 @@ mod verb {
             ("CI", Verb::CI),
             ("doc", Verb::Documentation),
             ("docs", Verb::Documentation),
+            ("revert", Verb::Reverts),
         ];
         for tc in tcs {
             let body = format!("{} something", tc.0);
@@ -144,7 +173,7 @@ mod references {
         let body = "feat(commit): this links to #123\n\nSomething is here";
         let (repo, oid) = new_commit("filename", body)?;
         let commit: Commit = repo.find_commit(oid)?.into();
-        assert_eq!(vec![Reference(123)], commit.references(), "{body}");
+        assert_eq!(vec![Reference::Issue(123)], commit.references(), "{body}");
         Ok(())
     }
 
@@ -153,7 +182,7 @@ mod references {
         let body = "feat:something 23\n\n#123: is the ref";
         let (repo, oid) = new_commit("filename", body)?;
         let commit: Commit = repo.find_commit(oid)?.into();
-        assert_eq!(vec![Reference(123)], commit.references(), "{body}");
+        assert_eq!(vec![Reference::Issue(123)], commit.references(), "{body}");
         Ok(())
     }
 
@@ -162,7 +191,7 @@ mod references {
         let body = "feat:something 23\n\nRef #123";
         let (repo, oid) = new_commit("filename", body)?;
         let commit: Commit = repo.find_commit(oid)?.into();
-        assert_eq!(vec![Reference(123)], commit.references());
+        assert_eq!(vec![Reference::Issue(123)], commit.references());
         Ok(())
     }
 
@@ -171,7 +200,44 @@ mod references {
         let body = "feat:something 23\n\nRef #123, Close #456";
         let (repo, oid) = new_commit("filename", body)?;
         let commit: Commit = repo.find_commit(oid)?.into();
-        assert_eq!(vec![Reference(123), Reference(456)], commit.references());
+        assert_eq!(
+            vec![Reference::Issue(123), Reference::Issue(456)],
+            commit.references()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tracker_keys() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(TRACKERS.parse()?);
+        let body = "feat: PROJ-12 something\n\nRef #123, ENG-7 and PROJ-12.\nPROJ-13";
+        let (repo, oid) = new_commit("filename", body)?;
+        let commit = Commit::new(repo.find_commit(oid)?, config);
+        let want = vec![
+            Reference::Issue(123),
+            Reference::Tracker {
+                key: "PROJ-12".to_owned(),
+                url: "https://jira.example.com/browse/PROJ-12".to_owned(),
+            },
+            Reference::Tracker {
+                key: "PROJ-13".to_owned(),
+                url: "https://jira.example.com/browse/PROJ-13".to_owned(),
+            },
+            Reference::Tracker {
+                key: "ENG-7".to_owned(),
+                url: "https://linear.app/team/issue/ENG-7".to_owned(),
+            },
+        ];
+        assert_eq!(want, commit.references());
+        Ok(())
+    }
+
+    #[test]
+    fn tracker_not_configured() -> Result<(), Box<dyn std::error::Error>> {
+        let body = "feat: PROJ-12 something\n\nRef #123";
+        let (repo, oid) = new_commit("filename", body)?;
+        let commit: Commit = repo.find_commit(oid)?.into();
+        assert_eq!(vec![Reference::Issue(123)], commit.references());
         Ok(())
     }
 }
@@ -240,6 +306,84 @@ mod subjects {
     }
 }
 
+#[cfg(test)]
+mod normalised_subjects {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const SCOPES: &str = r#"
+scopes:
+  case_insensitive: true
+  aliases:
+    Repository: repo
+    srv: Server
+  allowed: [repo, server]
+"#;
+
+    #[test]
+    fn aliases() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(SCOPES.parse()?);
+        let tcs = vec![
+            ("ref(repo): something", vec!["repo"]),
+            ("ref(Repo): something", vec!["repo"]),
+            ("ref(repository): something", vec!["repo"]),
+            ("ref(REPOSITORY, srv): something", vec!["repo", "server"]),
+            ("ref(repo, Repository): something", vec!["repo"]),
+            ("ref(Docs): something", vec!["docs"]),
+            ("ref(repo, ,srv,): something", vec!["repo", "server"]),
+        ];
+        for (body, want) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, commit.subjects().ok_or("no subjects")?, "{body}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn case_sensitive() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = "scopes:\n  aliases:\n    repository: repo".parse()?;
+        let config = Rc::new(config);
+        let tcs = vec![
+            ("ref(Repo): something", vec!["Repo"]),
+            ("ref(repository): something", vec!["repo"]),
+            ("ref(Repository): something", vec!["Repository"]),
+        ];
+        for (body, want) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, commit.subjects().ok_or("no subjects")?, "{body}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_subjects() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(SCOPES.parse()?);
+        let tcs = vec![
+            ("ref: something", vec![]),
+            ("ref(Repository, srv): something", vec![]),
+            ("ref(repo, docs, ci): something", vec!["docs", "ci"]),
+        ];
+        for (body, want) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, commit.unknown_subjects(), "{body}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn display() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(SCOPES.parse()?);
+        let body = "feat(Repository,SRV): this is a test";
+        let (repo, oid) = new_commit("filename", body)?;
+        let commit = Commit::new(repo.find_commit(oid)?, config);
+        assert_eq!("**repo, server:** This is a test", format!("{commit}"));
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod is_breaking {
     use super::*;
@@ -435,4 +579,459 @@ mod display_fmt {
 
         Ok(())
     }
+    #[test]
+    fn tracker_keys() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(TRACKERS.parse()?);
+        let tcs = vec![
+            (
+                "feat: PROJ-12 this is a test",
+                "This is a test (ref [PROJ-12](https://jira.example.com/browse/PROJ-12))",
+            ),
+            (
+                "fix(repo): this is a test [PROJ-12]",
+                "**repo:** This is a test (ref [PROJ-12](https://jira.example.com/browse/PROJ-12))",
+            ),
+            (
+                "feat: this (ENG-1) is a test ref #3",
+                "This is a test (ref #3, ref [ENG-1](https://linear.app/team/issue/ENG-1))",
+            ),
+            (
+                "feat: this is a test\n\nCloses PROJ-1",
+                "This is a test (ref [PROJ-1](https://jira.example.com/browse/PROJ-1))",
+            ),
+        ];
+        for (body, want) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, format!("{commit}"));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod pull_request {
+    use super::*;
+    use crate::workspace::pull_request::PullRequest;
+    use pretty_assertions::assert_eq;
+
+    fn pull_request(title: &str, labels: &[&str]) -> PullRequest {
+        PullRequest {
+            number: 42,
+            title: title.to_owned(),
+            author: "someone".to_owned(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn title_from_pull_request() -> Result<(), Box<dyn std::error::Error>> {
+        let body = "wip\n\nBody ref #12";
+        let (repo, oid) = new_commit("filename", body)?;
+        let mut commit: Commit = repo.find_commit(oid)?.into();
+        commit.set_pull_request(pull_request("feat(repo): add a feature", &[]));
+
+        assert_eq!(Verb::Feature, commit.verb());
+        assert_eq!(Some(vec!["repo".to_owned()]), commit.subjects());
+        assert_eq!(
+            vec![Reference::Issue(42), Reference::Issue(12)],
+            commit.references()
+        );
+        assert_eq!(
+            "**repo:** Add a feature (ref #42, ref #12)",
+            format!("{commit}")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn number_in_title() -> Result<(), Box<dyn std::error::Error>> {
+        let (repo, oid) = new_commit("filename", "fix: something (#42)")?;
+        let mut commit: Commit = repo.find_commit(oid)?.into();
+        commit.set_pull_request(pull_request("fix: something (ref #42)", &[]));
+        assert_eq!(vec![Reference::Issue(42)], commit.references());
+        Ok(())
+    }
+
+    #[test]
+    fn verb_from_labels() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Config = "labels:\n  bug: fix\n  enhancement: Feature".parse()?;
+        let config = Rc::new(config);
+        let tcs = vec![
+            (vec!["bug"], Verb::Fix),
+            (vec!["enhancement"], Verb::Feature),
+            (vec!["other", "enhancement"], Verb::Feature),
+            (vec!["other"], Verb::Documentation),
+            (vec![], Verb::Documentation),
+        ];
+        for (labels, want) in tcs {
+            let (repo, oid) = new_commit("filename", "feat: something")?;
+            let mut commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            commit.set_pull_request(pull_request("docs: something", &labels));
+            assert_eq!(want, commit.verb(), "{labels:?}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod without_subjects {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn display() -> Result<(), Box<dyn std::error::Error>> {
+        let tcs = vec![
+            ("feat(repo, server): this is a test", "This is a test"),
+            (
+                "feat(repo)!: this is a test",
+                "This is a test [**BREAKING CHANGE**]",
+            ),
+            ("feat: this is a test ref #1", "This is a test (ref #1)"),
+        ];
+        for (body, want) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit: Commit = repo.find_commit(oid)?.into();
+            assert_eq!(want, format!("{}", commit.without_subjects()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod is_ignored {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const RULES: &str = r#"
+scopes:
+  aliases:
+    continuous: ci
+ignore:
+  - verb: chore
+  - scope: ci
+  - summary: '(?i)^wip\b'
+  - verb: docs
+    scope: internal
+  - trailer: 'Changelog: skip'
+  - trailer: No-Changelog
+  - author: '\[bot\]$'
+  - email: '@bots\.example\.com$'
+"#;
+
+    #[test]
+    fn by_message() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(RULES.parse()?);
+        let tcs = vec![
+            ("feat: something", false),
+            ("chore: something", true),
+            ("Chore(deps): something", true),
+            ("feat(ci): something", true),
+            ("fix(continuous): something", true),
+            ("feat(repo, ci): something", true),
+            ("WIP something", true),
+            ("wip: something", true),
+            ("wipe the cache", false),
+            ("docs(internal): something", true),
+            ("docs(public): something", false),
+            ("feat(internal): something", false),
+            ("feat: something\n\nBody.\n\nChangelog: skip", true),
+            ("feat: something\n\nBody.\n\nchangelog: Skip", true),
+            ("feat: something\n\nBody.\n\nChangelog: include", false),
+            ("feat: something\n\nBody.\n\nNo-Changelog: true", true),
+            (
+                "feat: something\n\nChangelog: skip is in the body.\n\nOther: trailer",
+                false,
+            ),
+        ];
+        for (body, want) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, commit.is_ignored(), "{body}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn by_author() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(RULES.parse()?);
+        let tcs = vec![
+            ("name", "name@example.com", false),
+            ("dependabot[bot]", "bot@example.com", true),
+            ("someone", "someone@bots.example.com", true),
+        ];
+        let (_dir, repo) = crate::common_test::repo_init();
+        for (name, email, want) in tcs {
+            let parent = repo.head()?.peel_to_commit()?;
+            let sig = git2::Signature::now(name, email)?;
+            let tree = parent.tree()?;
+            let oid = repo.commit(None, &sig, &sig, "feat: other", &tree, &[&parent])?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            assert_eq!(want, commit.is_ignored(), "{name} <{email}>");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn no_rules() -> Result<(), Box<dyn std::error::Error>> {
+        let (repo, oid) = new_commit("filename", "chore: something")?;
+        let commit: Commit = repo.find_commit(oid)?.into();
+        assert!(!commit.is_ignored());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod merged_branch {
+    use super::*;
+    use crate::common_test;
+    use crate::workspace::remote::RemoteInfo;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn from_merge_commit() -> Result<(), Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new("merges: pull-requests".parse()?);
+        let (_dir, repo) = common_test::repo_init();
+        let base = repo.head()?.peel_to_commit()?.id();
+        let first = common_test::branch_commit(&repo, base, "wip");
+        let tip = common_test::branch_commit(&repo, first, "fix(repo): the branch tip");
+        let branch = [repo.find_commit(first)?, repo.find_commit(tip)?];
+
+        let tcs = vec![
+            (
+                "Merge pull request #42 from arsham/branch\n\nfeat(repo): the title",
+                "**repo:** The title (ref #42)",
+                Verb::Feature,
+            ),
+            (
+                "Merge pull request #43 from arsham/branch",
+                "**repo:** The branch tip (ref #43)",
+                Verb::Fix,
+            ),
+            (
+                "Merge pull request #44 from arsham/branch\n\nUpdate the docs",
+                "Update the docs (ref #44)",
+                Verb::Fix,
+            ),
+            (
+                "Merge branch 'feature' into 'main'\n\nfeat: the merge request\n\n\
+                 See merge request group/project!12",
+                "The merge request (ref !12)",
+                Verb::Feature,
+            ),
+            (
+                "Merge branch 'feature' into 'main'\n\nSee merge request group/project!13",
+                "**repo:** The branch tip (ref !13)",
+                Verb::Fix,
+            ),
+            (
+                "Merge branch 'other'",
+                "**repo:** The branch tip",
+                Verb::Fix,
+            ),
+        ];
+        for (msg, want, verb) in tcs {
+            let oid = common_test::merge(&repo, tip, msg);
+            let mut commit = Commit::new(repo.find_commit(oid)?, Rc::clone(&config));
+            commit.set_branch(&branch);
+            assert_eq!(want, format!("{commit}"), "{msg}");
+            assert_eq!(verb, commit.verb(), "{msg}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn merge_request_link() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config: Config = "merges: pull-requests\nlinks: true".parse()?;
+        config.remote = RemoteInfo::parse("git@gitlab.com:group/project.git");
+        let config = Rc::new(config);
+        let (_dir, repo) = common_test::repo_init();
+        let base = repo.head()?.peel_to_commit()?.id();
+        let tip = common_test::branch_commit(&repo, base, "fix: the branch tip");
+        let msg = "Merge branch 'feature' into 'main'\n\nSee merge request group/project!12";
+        let oid = common_test::merge(&repo, tip, msg);
+
+        let commit = Commit::new(repo.find_commit(oid)?, config);
+        assert_eq!(
+            vec!["[!12](https://gitlab.com/group/project/-/merge_requests/12)"],
+            commit
+                .references()
+                .iter()
+                .map(|r| r.issue_ref(commit.config.links()))
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn other_policies() -> Result<(), Box<dyn std::error::Error>> {
+        let (_dir, repo) = common_test::repo_init();
+        let base = repo.head()?.peel_to_commit()?.id();
+        let branch = common_test::branch_commit(&repo, base, "fix: the branch tip");
+        let msg = "Merge pull request #42 from arsham/branch\n\nfeat: the title";
+        let oid = common_test::merge(&repo, branch, msg);
+
+        let mut commit: Commit = repo.find_commit(oid)?.into();
+        commit.set_branch(&[repo.find_commit(branch)?]);
+        assert_eq!(Verb::Misc, commit.verb());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod reverts {
+    use super::*;
+    use crate::common_test;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn detection() -> Result<(), Box<dyn std::error::Error>> {
+        let tcs = vec![
+            ("feat: something", false, None),
+            ("Revert \"feat: something\"", true, None),
+            (
+                "Revert \"feat: something\"\n\nThis reverts commit 1234567890abcdef.",
+                true,
+                Some("1234567890abcdef"),
+            ),
+            (
+                "fix: undo the change\n\nThis reverts commit abcdef1.",
+                true,
+                Some("abcdef1"),
+            ),
+            ("fix: revert \"something\"", false, None),
+        ];
+        for (body, is_revert, id) in tcs {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit: Commit = repo.find_commit(oid)?.into();
+            assert_eq!(is_revert, commit.is_revert(), "{body}");
+            assert_eq!(id, commit.reverted_id(), "{body}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn display() -> Result<(), Box<dyn std::error::Error>> {
+        let body = "Revert \"feat(repo): add something\"\n\nThis reverts commit abcdef1.";
+        let (repo, oid) = new_commit("filename", body)?;
+        let commit: Commit = repo.find_commit(oid)?.into();
+        assert_eq!(Verb::Reverts, commit.verb());
+        assert_eq!("**repo:** Add something", format!("{commit}"));
+        Ok(())
+    }
+
+    #[test]
+    fn reverts_other() -> Result<(), Box<dyn std::error::Error>> {
+        let (_dir, repo) = common_test::repo_init();
+        let (oid, _) = common_test::commit(&repo, "file1", Some("feat: something"));
+        let original: Commit = repo.find_commit(oid)?.into();
+        let (oid, _) = common_test::commit(&repo, "file2", Some("feat: other"));
+        let other: Commit = repo.find_commit(oid)?.into();
+
+        let msg = format!(
+            "Revert \"feat: something\"\n\nThis reverts commit {}.",
+            original.id()
+        );
+        let (oid, _) = common_test::commit(&repo, "file3", Some(&msg));
+        let by_id: Commit = repo.find_commit(oid)?.into();
+        assert!(by_id.reverts(&original));
+        assert!(!by_id.reverts(&other));
+
+        let msg = format!("fix: undo\n\nThis reverts commit {}.", original.short_id());
+        let (oid, _) = common_test::commit(&repo, "file4", Some(&msg));
+        let by_short_id: Commit = repo.find_commit(oid)?.into();
+        assert!(by_short_id.reverts(&original));
+
+        let (oid, _) = common_test::commit(&repo, "file5", Some("Revert \"feat: other\""));
+        let by_summary: Commit = repo.find_commit(oid)?.into();
+        assert!(by_summary.reverts(&other));
+        assert!(!by_summary.reverts(&original));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod expand {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn squashed_body() -> Result<(), Box<dyn std::error::Error>> {
+        let body = "Add things (ref #12)\n\n* feat(repo): add a\n* fix: b for PROJ-1\n* some notes\n\nBREAKING CHANGE: c";
+        let (repo, oid) = new_commit("filename", body)?;
+        let commit: Commit = repo.find_commit(oid)?.into();
+        let commits = commit.expand();
+        assert_eq!(2, commits.len());
+
+        assert_eq!(Verb::Feature, commits[0].verb());
+        assert_eq!("**repo:** Add a (ref #12)", format!("{}", commits[0]));
+        assert!(!commits[0].is_breaking());
+        assert_eq!(Verb::Fix, commits[1].verb());
+        assert_eq!("B for PROJ-1 (ref #12)", format!("{}", commits[1]));
+        assert_eq!(commits[0].id(), commits[1].id());
+        Ok(())
+    }
+
+    #[test]
+    fn not_squashed() -> Result<(), Box<dyn std::error::Error>> {
+        for body in ["feat: something", "feat: something\n\n- a list\n- of notes"] {
+            let (repo, oid) = new_commit("filename", body)?;
+            let commit: Commit = repo.find_commit(oid)?.into();
+            let commits = commit.expand();
+            assert_eq!(1, commits.len(), "{body}");
+            assert_eq!("Something", format!("{}", commits[0]), "{body}");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod links {
+    use super::*;
+    use crate::workspace::remote::RemoteInfo;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn commit_and_issues() -> Result<(), Box<dyn std::error::Error>> {
+        let tcs = vec![
+            (
+                "git@github.com:arsham/shark.git",
+                "https://github.com/arsham/shark/issues/3",
+                "https://github.com/arsham/shark/commit/",
+            ),
+            (
+                "git@bitbucket.org:team/repo.git",
+                "https://bitbucket.org/team/repo/issues/3",
+                "https://bitbucket.org/team/repo/commits/",
+            ),
+            (
+                "https://dev.azure.com/org/project/_git/repo",
+                "https://dev.azure.com/org/project/_workitems/edit/3",
+                "https://dev.azure.com/org/project/_git/repo/commit/",
+            ),
+        ];
+        for (url, issue, commit_url) in tcs {
+            let mut config: Config = "links: true".parse()?;
+            config.remote = RemoteInfo::parse(url);
+            let (repo, oid) = new_commit("filename", "feat: something ref #3")?;
+            let commit = Commit::new(repo.find_commit(oid)?, Rc::new(config));
+            let want = format!(
+                "Something (ref [#3]({issue}), [{}]({commit_url}{oid}))",
+                commit.short_id()
+            );
+            assert_eq!(want, format!("{commit}"), "{url}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn disabled() -> Result<(), Box<dyn std::error::Error>> {
+        let mut config: Config = "".parse()?;
+        config.remote = RemoteInfo::parse("git@bitbucket.org:team/repo.git");
+        let (repo, oid) = new_commit("filename", "feat: something ref #3")?;
+        let commit = Commit::new(repo.find_commit(oid)?, Rc::new(config));
+        assert_eq!("Something (ref #3)", format!("{commit}"));
+        Ok(())
+    }
 }
@@ -21,4 +21,55 @@ pub enum GRError {
 
     #[error("Could not get the url of the repository at '{0}'")]
     URLError(String),
+
+    /// Returned when the config file can't be read.
+    #[error("Could not read the config file at '{0}'")]
+    ConfigRead(String, #[source] std::io::Error),
+
+    /// Returned when the commits have scopes that are not allowed.
+    #[error("Unknown scopes: {0}")]
+    UnknownScopes(String),
+
+    /// Returned when the token for the backend is not provided.
+    #[error("The {0} environment variable is not set")]
+    MissingToken(&'static str),
+
+    /// Returned when an option is not supported by the backend.
+    #[error("{0} is not supported on {1}")]
+    Unsupported(&'static str, &'static str),
+
+    /// Returned when the service of the remote can't be told from its host.
+    #[error(
+        "Could not tell where to publish the releases of '{0}', set --github-host, --gitlab-url or \
+         --gitea-url"
+    )]
+    UnknownBackend(String),
+
+    /// Returned when there is no draft release for the tag.
+    #[error("Could not find a draft release for the '{0}' tag")]
+    DraftNotFound(String),
+
+    /// Returned when the changes to the release are not confirmed.
+    #[error("The release of the '{0}' tag would change, use --yes to apply the changes")]
+    ReleaseChanged(String),
+
+    /// Returned when the user declines the changes to the release.
+    #[error("Aborted")]
+    Aborted,
+
+    /// Returned when an asset can't be read.
+    #[error("Could not read the asset at '{0}'")]
+    AssetRead(String, #[source] Option<std::io::Error>),
+
+    /// Returned when the glob pattern of the assets doesn't match any files.
+    #[error("No assets found for '{0}'")]
+    AssetNotFound(String),
+
+    /// Returned when the glob pattern of the assets is not valid.
+    #[error("Invalid asset pattern: {0}")]
+    AssetPattern(#[from] glob::PatternError),
+
+    /// Returned when the contents of the config file is not valid.
+    #[error("Invalid config: {0}")]
+    ConfigParse(#[from] serde_yaml::Error),
 }
@@ -1,4 +1,7 @@
 pub mod commit;
 pub mod errors;
+pub mod pull_request;
 pub mod release;
+pub mod remote;
 pub mod repository;
+pub mod tag;
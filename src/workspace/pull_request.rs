@@ -0,0 +1,8 @@
+/// A PullRequest is the pull request on github that a commit was merged with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest {
+    pub number: u64,
+    pub title: String,
+    pub author: String,
+    pub labels: Vec<String>,
+}
@@ -2,52 +2,338 @@
 #[path = "./release_test.rs"]
 mod release_test;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Display;
+use std::rc::Rc;
 
 use super::commit::{Commit, Verb};
+use super::pull_request::PullRequest;
+use crate::config::{Config, GroupBy};
 
-/// A Release represents one of more Commits, grouped by the verbs in their title.
+/// The title of the section for the commits that don't belong to any of the categories.
+const OTHER_CHANGES: &str = "Other Changes";
+
+/// The title of the section for the commits that don't have a scope.
+const OTHER: &str = "Other";
+
+/// A Release represents one of more Commits, grouped by the verbs or the scopes in their title, or
+/// by the labels of their pull requests.
 pub struct Release<'a> {
     commits: Vec<Commit<'a>>,
+    config: Rc<Config>,
 }
 
 impl<'a> Release<'a> {
-    #[allow(dead_code)]
-    pub fn new(commits: Vec<Commit<'a>>) -> Self {
-        Release { commits }
+    /// Creates a new Release from the commits that are not ignored by the config. The commits
+    /// should be in chronological order. When a commit and its revert are both in the release,
+    /// neither of them are listed. If set in the config, the squash merged commits are expanded
+    /// before the ignore rules are checked.
+    pub fn new(mut commits: Vec<Commit<'a>>, config: Rc<Config>) -> Self {
+        cancel_reverts(&mut commits);
+        if config.expand_squashed {
+            commits = commits.into_iter().flat_map(Commit::expand).collect();
+        }
+        commits.retain(|commit| !commit.is_ignored());
+        Release { commits, config }
+    }
+
+    /// Returns the ids of all commits in this release.
+    pub fn commit_ids(&self) -> Vec<git2::Oid> {
+        self.commits.iter().map(Commit::id).collect()
+    }
+
+    /// Assigns the pull requests to their commits, keyed by the commit ids. When more than one
+    /// commit belongs to the same pull request, only the first one is kept.
+    pub fn set_pull_requests(&mut self, pull_requests: HashMap<git2::Oid, PullRequest>) {
+        let mut owners = HashMap::new();
+        self.commits.retain_mut(|commit| {
+            let Some(pr) = pull_requests.get(&commit.id()) else {
+                return true;
+            };
+            // Expanded commits share the same id and are all kept.
+            if *owners.entry(pr.number).or_insert_with(|| commit.id()) != commit.id() {
+                return false;
+            }
+            commit.set_pull_request(pr.clone());
+            true
+        });
+    }
+
+    /// Removes the commits that have the same changes as an older commit in the release. The
+    /// changes are compared by their patch ids, keyed by the commit ids, and by the
+    /// `(cherry picked from commit ...)` line in the message. The older commit is marked as
+    /// back-ported if it was cherry-picked.
+    pub fn remove_duplicates(&mut self, patch_ids: &HashMap<git2::Oid, git2::Oid>) {
+        let mut removed = HashSet::new();
+        for j in 0..self.commits.len() {
+            let id = self.commits[j].id();
+            let patch_id = patch_ids.get(&id);
+            let original = (0..j).find(|&i| {
+                let other = &self.commits[i];
+                !removed.contains(&i)
+                    && other.id() != id
+                    && (patch_id.is_some() && patch_id == patch_ids.get(&other.id())
+                        || other.is_cherry_pick_of(&self.commits[j]))
+            });
+            if let Some(i) = original {
+                removed.insert(j);
+                if self.commits[i].is_cherry_pick_of(&self.commits[j]) {
+                    self.commits[i].set_backported();
+                }
+            }
+        }
+        let mut i = 0;
+        self.commits.retain(|_| {
+            i += 1;
+            !removed.contains(&(i - 1))
+        });
+    }
+
+    /// Removes the commits that have any of the released patch ids. The patch ids of the commits
+    /// are keyed by the commit ids.
+    pub fn remove_released(
+        &mut self,
+        patch_ids: &HashMap<git2::Oid, git2::Oid>,
+        released: &HashSet<git2::Oid>,
+    ) {
+        self.commits.retain(|commit| {
+            patch_ids
+                .get(&commit.id())
+                .is_none_or(|patch_id| !released.contains(patch_id))
+        });
+    }
+
+    /// Returns true if the labels or the author of the commit's pull request are excluded from
+    /// the release.
+    fn is_excluded(&self, commit: &Commit) -> bool {
+        self.config
+            .changelog
+            .exclude
+            .matches(commit.labels(), commit.author())
+    }
+
+    /// Returns the scopes that are not allowed, with the commits that have them.
+    pub fn unknown_scopes(&self) -> Vec<(String, &Commit<'a>)> {
+        self.included()
+            .into_iter()
+            .flat_map(|commit| {
+                commit
+                    .unknown_subjects()
+                    .into_iter()
+                    .map(move |scope| (scope, commit))
+            })
+            .collect()
+    }
+
+    /// Returns the commits that are not excluded from the release.
+    fn included(&self) -> Vec<&Commit<'a>> {
+        self.commits
+            .iter()
+            .filter(|c| !self.is_excluded(c))
+            .collect()
     }
 
     pub fn get_verb_groups(&self) -> HashMap<Verb, Vec<&Commit<'a>>> {
-        let mut map: HashMap<Verb, Vec<&Commit>> = HashMap::with_capacity(self.commits.len());
-        for commit in &self.commits {
+        Self::verb_groups(self.included())
+    }
+
+    /// Returns the commits grouped by the labels of their pull requests, in the order of the
+    /// configured categories. Excluded commits are left out, and the ones that don't
+    /// belong to any categories are put in the "Other Changes" section.
+    pub fn get_label_groups(&self) -> Vec<(String, Vec<&Commit<'a>>)> {
+        self.label_groups(self.included())
+    }
+
+    /// Returns the commits grouped by their scopes in alphabetical order. Commits with more than
+    /// one scope are listed under each of them, and the ones without a scope are put in the
+    /// "Other" section.
+    pub fn get_scope_groups(&self) -> Vec<(String, Vec<&Commit<'a>>)> {
+        Self::scope_groups(self.included())
+    }
+
+    fn verb_groups<'c>(commits: Vec<&'c Commit<'a>>) -> HashMap<Verb, Vec<&'c Commit<'a>>> {
+        let mut map: HashMap<Verb, Vec<&Commit>> = HashMap::with_capacity(commits.len());
+        for commit in commits {
             let verb = commit.verb();
             map.entry(verb).or_default().push(commit);
         }
         map
     }
+
+    fn label_groups<'c>(&self, commits: Vec<&'c Commit<'a>>) -> Vec<(String, Vec<&'c Commit<'a>>)> {
+        let changelog = &self.config.changelog;
+        let mut groups: Vec<(String, Vec<&Commit>)> = changelog
+            .categories
+            .iter()
+            .map(|category| (category.title.clone(), vec![]))
+            .collect();
+        let mut other = vec![];
+        for commit in commits {
+            let (labels, author) = (commit.labels(), commit.author());
+            match changelog
+                .categories
+                .iter()
+                .position(|c| c.matches(labels, author))
+            {
+                Some(i) => groups[i].1.push(commit),
+                None => other.push(commit),
+            }
+        }
+        groups.push((OTHER_CHANGES.to_owned(), other));
+        groups.retain(|(_, commits)| !commits.is_empty());
+        groups
+    }
+
+    fn scope_groups<'c>(commits: Vec<&'c Commit<'a>>) -> Vec<(String, Vec<&'c Commit<'a>>)> {
+        let mut map: BTreeMap<String, Vec<&Commit>> = BTreeMap::new();
+        let mut other = vec![];
+        for commit in commits {
+            match commit.subjects() {
+                Some(subjects) => {
+                    for subject in subjects {
+                        map.entry(subject).or_default().push(commit);
+                    }
+                },
+                None => other.push(commit),
+            }
+        }
+        let mut groups: Vec<_> = map.into_iter().collect();
+        if !other.is_empty() {
+            groups.push((OTHER.to_owned(), other));
+        }
+        groups
+    }
+
+    /// Groups the given commits. The verb groups are sorted by the order of the verbs.
+    fn group<'c>(
+        &self,
+        commits: Vec<&'c Commit<'a>>,
+        by: GroupBy,
+    ) -> Vec<(String, Vec<&'c Commit<'a>>)> {
+        match by {
+            GroupBy::Verb => Self::sorted_verb_groups(Self::verb_groups(commits)),
+            GroupBy::Label => self.label_groups(commits),
+            GroupBy::Scope => Self::scope_groups(commits),
+        }
+    }
+
+    fn sorted_verb_groups<'c>(
+        groups: HashMap<Verb, Vec<&'c Commit<'a>>>,
+    ) -> Vec<(String, Vec<&'c Commit<'a>>)> {
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+        groups
+            .into_iter()
+            .map(|(verb, commits)| (format!("{verb:?}"), commits))
+            .collect()
+    }
+
+    /// Returns the sections of the release based on the configured grouping. If the sections are
+    /// subgrouped, the commits of each section are in its subsections.
+    pub fn sections(&self) -> Vec<Section<'_, 'a>> {
+        let groups = match self.config.group_by() {
+            GroupBy::Verb => Self::sorted_verb_groups(self.get_verb_groups()),
+            GroupBy::Label => self.get_label_groups(),
+            GroupBy::Scope => self.get_scope_groups(),
+        };
+        let subgroup_by = self.config.subgroup_by();
+        groups
+            .into_iter()
+            .map(|(title, commits)| match subgroup_by {
+                Some(by) => Section {
+                    title,
+                    commits: vec![],
+                    subsections: self
+                        .group(commits, by)
+                        .into_iter()
+                        .map(|(title, commits)| Section::new(title, commits))
+                        .collect(),
+                },
+                None => Section::new(title, commits),
+            })
+            .collect()
+    }
+}
+
+/// Removes the reverts and their reverted commits. The newest reverts are checked first, so a
+/// revert of a revert brings back the original commit.
+fn cancel_reverts(commits: &mut Vec<Commit>) {
+    let mut cancelled = HashSet::new();
+    for i in (0..commits.len()).rev() {
+        if cancelled.contains(&i) || !commits[i].is_revert() {
+            continue;
+        }
+        let reverted = (0..i)
+            .rev()
+            .find(|j| !cancelled.contains(j) && commits[i].reverts(&commits[*j]));
+        if let Some(j) = reverted {
+            cancelled.insert(i);
+            cancelled.insert(j);
+        }
+    }
+    let mut i = 0;
+    commits.retain(|_| {
+        i += 1;
+        !cancelled.contains(&(i - 1))
+    });
+}
+
+/// A Section is a titled group of commits in the release. When the sections are subgrouped, the
+/// commits are in the subsections instead.
+#[derive(Debug)]
+pub struct Section<'c, 'a> {
+    pub title: String,
+    pub commits: Vec<&'c Commit<'a>>,
+    pub subsections: Vec<Section<'c, 'a>>,
+}
+
+impl<'c, 'a> Section<'c, 'a> {
+    fn new(title: String, commits: Vec<&'c Commit<'a>>) -> Self {
+        Section {
+            title,
+            commits,
+            subsections: vec![],
+        }
+    }
 }
 
 impl<'a> From<Vec<git2::Commit<'a>>> for Release<'a> {
     fn from(commits: Vec<git2::Commit<'a>>) -> Self {
         let commits = commits.into_iter().map(Commit::from).collect();
-        Release { commits }
+        Release::new(commits, Rc::default())
     }
 }
 
 impl<'a> Display for Release<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The scope is already in the title of the section.
+        let by_scope = self.config.group_by() == GroupBy::Scope
+            || self.config.subgroup_by() == Some(GroupBy::Scope);
+        let entries = |commits: Vec<&Commit>| {
+            commits
+                .into_iter()
+                .map(|commit| match by_scope {
+                    true => format!("- {}", commit.without_subjects()),
+                    false => format!("- {commit}"),
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        };
+
         let mut groups = vec![];
-        for (verb, commits) in self.get_verb_groups() {
+        for section in self.sections() {
             let mut contents = String::new();
-            contents.push_str(&format!("### {verb:?}\n\n"));
-            contents.push_str(
-                &commits
+            contents.push_str(&format!("### {}\n\n", section.title));
+            if section.subsections.is_empty() {
+                contents.push_str(&entries(section.commits));
+            } else {
+                let subsections = section
+                    .subsections
                     .into_iter()
-                    .map(|commit| format!("- {commit}"))
-                    .collect::<Vec<String>>()
-                    .join("\n"),
-            );
+                    .map(|sub| format!("#### {}\n\n{}", sub.title, entries(sub.commits)))
+                    .collect::<Vec<String>>();
+                contents.push_str(&subsections.join("\n\n"));
+            }
             groups.push(contents);
         }
         write!(f, "{}", groups.join("\n\n"))?;
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 
 use difference::Changeset;
 
@@ -13,7 +14,7 @@ mod get_verb_groups {
 
     #[test]
     fn no_commits() -> Result<(), Box<dyn std::error::Error>> {
-        let release = Release::new(vec![]);
+        let release = Release::new(vec![], Rc::default());
         assert!(release.get_verb_groups().is_empty());
         Ok(())
     }
@@ -30,7 +31,7 @@ mod get_verb_groups {
         let got = got
             .get(&Verb::Feature)
             .ok_or("not found")?
-            .get(0)
+            .first()
             .ok_or("not found")?;
         assert_eq!(commit, **got);
         Ok(())
@@ -142,3 +143,508 @@ mod display_fmt {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod set_pull_requests {
+    use super::*;
+    use crate::workspace::pull_request::PullRequest;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn merges_commits_of_same_pull_request() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+
+        let (oid1, _) = common_test::commit(&repo, "filename1", Some("wip"));
+        let (oid2, _) = common_test::commit(&repo, "filename2", Some("more wip"));
+        let (oid3, _) = common_test::commit(&repo, "filename3", Some("fix: a bug"));
+        let commits = vec![
+            repo.find_commit(oid1)?,
+            repo.find_commit(oid2)?,
+            repo.find_commit(oid3)?,
+        ];
+        let mut release: Release = commits.into();
+
+        let pr = PullRequest {
+            number: 7,
+            title: "feat(repo): a new feature".to_owned(),
+            author: "someone".to_owned(),
+            labels: vec![],
+        };
+        let mut prs = HashMap::new();
+        prs.insert(oid1, pr.clone());
+        prs.insert(oid2, pr);
+        release.set_pull_requests(prs);
+
+        assert_eq!(vec![oid1, oid3], release.commit_ids());
+        let want = vec![
+            "### Feature\n\n- **repo:** A new feature (ref #7)",
+            "### Fix\n\n- A bug",
+        ];
+        let want1 = want.join("\n\n");
+        let want2 = want.into_iter().rev().collect::<Vec<&str>>().join("\n\n");
+        let got = format!("{release}");
+        assert!(
+            want1 == got || want2 == got,
+            "{}",
+            Changeset::new(&want1, &got, "")
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod get_label_groups {
+    use super::*;
+    use crate::config::Config;
+    use crate::workspace::pull_request::PullRequest;
+    use pretty_assertions::assert_eq;
+
+    const CHANGELOG: &str = r#"
+group_by: label
+changelog:
+  exclude:
+    labels: [skip-changelog]
+  categories:
+    - title: Breaking Changes
+      labels: [breaking]
+    - title: Features
+      labels: [enhancement, feature]
+      exclude:
+        labels: [internal]
+    - title: Bug Fixes
+      labels: [bug]
+"#;
+
+    #[test]
+    fn by_labels() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let config: Rc<Config> = Rc::new(CHANGELOG.parse()?);
+
+        let prs = vec![
+            ("add login", vec!["enhancement"]),
+            ("fix crash", vec!["bug"]),
+            ("drop api", vec!["breaking", "enhancement"]),
+            ("bump deps", vec!["skip-changelog", "bug"]),
+            ("refactor internals", vec!["feature", "internal"]),
+            ("update readme", vec![]),
+        ];
+        let mut commits = vec![];
+        let mut pull_requests = HashMap::new();
+        for (i, (title, labels)) in prs.into_iter().enumerate() {
+            let (oid, _) = common_test::commit(&repo, &format!("file{i}"), Some("wip"));
+            commits.push(Commit::new(repo.find_commit(oid)?, Rc::clone(&config)));
+            let pr = PullRequest {
+                number: i as u64 + 1,
+                title: title.to_owned(),
+                author: "someone".to_owned(),
+                labels: labels.into_iter().map(str::to_owned).collect(),
+            };
+            pull_requests.insert(oid, pr);
+        }
+        let mut release = Release::new(commits, config);
+        release.set_pull_requests(pull_requests);
+
+        let got: Vec<(String, Vec<String>)> = release
+            .get_label_groups()
+            .into_iter()
+            .map(|(title, commits)| (title, commits.iter().map(|c| c.to_string()).collect()))
+            .collect();
+        let want = vec![
+            (
+                "Breaking Changes".to_owned(),
+                vec!["Drop api (ref #3)".to_owned()],
+            ),
+            ("Features".to_owned(), vec!["Add login (ref #1)".to_owned()]),
+            (
+                "Bug Fixes".to_owned(),
+                vec!["Fix crash (ref #2)".to_owned()],
+            ),
+            (
+                "Other Changes".to_owned(),
+                vec![
+                    "Refactor internals (ref #5)".to_owned(),
+                    "Update readme (ref #6)".to_owned(),
+                ],
+            ),
+        ];
+        assert_eq!(want, got);
+
+        let want = "### Breaking Changes\n\n- Drop api (ref #3)\n\n### Features\n\n- Add login \
+                    (ref #1)\n\n### Bug Fixes\n\n- Fix crash (ref #2)\n\n### Other Changes\n\n- \
+                    Refactor internals (ref #5)\n- Update readme (ref #6)";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+
+    #[test]
+    fn catch_all_category() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let config: Config =
+            "changelog:\n  categories:\n    - title: Changes\n      labels: ['*']".parse()?;
+        let config = Rc::new(config);
+
+        let (oid, _) = common_test::commit(&repo, "file", Some("fix: something"));
+        let commits = vec![Commit::new(repo.find_commit(oid)?, Rc::clone(&config))];
+        let release = Release::new(commits, config);
+
+        let groups = release.get_label_groups();
+        assert_eq!(1, groups.len());
+        assert_eq!("Changes", groups[0].0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod exclude {
+    use super::*;
+    use crate::config::Config;
+    use crate::workspace::pull_request::PullRequest;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn by_author_and_label() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let config: Config =
+            "changelog:\n  exclude:\n    labels: [skip]\n    authors: ['dependabot[bot]']"
+                .parse()?;
+        let config = Rc::new(config);
+
+        let prs = vec![
+            ("feat: add login", "arsham", vec![]),
+            ("fix: bump deps", "dependabot[bot]", vec![]),
+            ("fix: a bug", "arsham", vec!["skip"]),
+        ];
+        let mut commits = vec![];
+        let mut pull_requests = HashMap::new();
+        for (i, (title, author, labels)) in prs.into_iter().enumerate() {
+            let (oid, _) = common_test::commit(&repo, &format!("file{i}"), Some(title));
+            commits.push(Commit::new(repo.find_commit(oid)?, Rc::clone(&config)));
+            let pr = PullRequest {
+                number: i as u64 + 1,
+                title: title.to_owned(),
+                author: author.to_owned(),
+                labels: labels.into_iter().map(str::to_owned).collect(),
+            };
+            pull_requests.insert(oid, pr);
+        }
+        let mut release = Release::new(commits, config);
+        release.set_pull_requests(pull_requests);
+
+        assert_eq!("### Feature\n\n- Add login (ref #1)", format!("{release}"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod scopes {
+    use super::*;
+    use crate::config::Config;
+    use pretty_assertions::assert_eq;
+
+    fn release<'a>(
+        repo: &'a git2::Repository,
+        config: &str,
+    ) -> Result<Release<'a>, Box<dyn std::error::Error>> {
+        let config: Rc<Config> = Rc::new(config.parse()?);
+        let msgs = vec![
+            "feat(repo): add tags",
+            "fix(server): handle timeouts",
+            "feat: add a flag",
+            "feat(server, repo): share the cache",
+            "fix(repo): read the remote",
+            "feat(,): add x",
+        ];
+        let mut commits = vec![];
+        for (i, msg) in msgs.into_iter().enumerate() {
+            let (oid, _) = common_test::commit(repo, &format!("file{i}"), Some(msg));
+            commits.push(Commit::new(repo.find_commit(oid)?, Rc::clone(&config)));
+        }
+        Ok(Release::new(commits, config))
+    }
+
+    #[test]
+    fn get_scope_groups() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let release = release(&repo, "")?;
+
+        let got: Vec<(String, Vec<String>)> = release
+            .get_scope_groups()
+            .into_iter()
+            .map(|(title, commits)| (title, commits.iter().map(|c| c.to_string()).collect()))
+            .collect();
+        let want = vec![
+            (
+                "repo".to_owned(),
+                vec![
+                    "**repo:** Add tags".to_owned(),
+                    "**server, repo:** Share the cache".to_owned(),
+                    "**repo:** Read the remote".to_owned(),
+                ],
+            ),
+            (
+                "server".to_owned(),
+                vec![
+                    "**server:** Handle timeouts".to_owned(),
+                    "**server, repo:** Share the cache".to_owned(),
+                ],
+            ),
+            (
+                "Other".to_owned(),
+                vec!["Add a flag".to_owned(), "Add x".to_owned()],
+            ),
+        ];
+        assert_eq!(want, got);
+        Ok(())
+    }
+
+    #[test]
+    fn group_by_scope() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let release = release(&repo, "group_by: scope")?;
+
+        let want = "### repo\n\n- Add tags\n- Share the cache\n- Read the remote\n\n### \
+                    server\n\n- Handle timeouts\n- Share the cache\n\n### Other\n\n- Add a flag\n- Add x";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+
+    #[test]
+    fn subgroup_verbs_by_scope() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let release = release(&repo, "subgroup_by: scope")?;
+
+        let want = "### Feature\n\n#### repo\n\n- Add tags\n- Share the cache\n\n#### \
+                    server\n\n- Share the cache\n\n#### Other\n\n- Add a flag\n- Add x\n\n### Fix\n\n#### \
+                    repo\n\n- Read the remote\n\n#### server\n\n- Handle timeouts";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+
+    #[test]
+    fn subgroup_scopes_by_verb() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let release = release(&repo, "group_by: scope\nsubgroup_by: verb")?;
+
+        let want = "### repo\n\n#### Feature\n\n- Add tags\n- Share the cache\n\n#### Fix\n\n- \
+                    Read the remote\n\n### server\n\n#### Feature\n\n- Share the cache\n\n#### \
+                    Fix\n\n- Handle timeouts\n\n### Other\n\n#### Feature\n\n- Add a flag\n- Add x";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod unknown_scopes {
+    use super::*;
+    use crate::config::Config;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn with_allowed_list() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let config: Config = "scopes:\n  allowed: [repo]".parse()?;
+        let config = Rc::new(config);
+
+        let msgs = vec![
+            "feat(repo): one",
+            "fix(server): two",
+            "feat(repo, ci): three",
+        ];
+        let mut commits = vec![];
+        let mut ids = vec![];
+        for (i, msg) in msgs.into_iter().enumerate() {
+            let (oid, _) = common_test::commit(&repo, &format!("file{i}"), Some(msg));
+            commits.push(Commit::new(repo.find_commit(oid)?, Rc::clone(&config)));
+            ids.push(oid);
+        }
+        let release = Release::new(commits, config);
+
+        let got: Vec<(String, git2::Oid)> = release
+            .unknown_scopes()
+            .into_iter()
+            .map(|(scope, commit)| (scope, commit.id()))
+            .collect();
+        let want = vec![("server".to_owned(), ids[1]), ("ci".to_owned(), ids[2])];
+        assert_eq!(want, got);
+        Ok(())
+    }
+
+    #[test]
+    fn without_allowed_list() -> Result<(), Box<dyn std::error::Error>> {
+        let body = "feat(anything): the title";
+        let (repo, oid) = new_commit("filename", body)?;
+        let release: Release = vec![repo.find_commit(oid)?].into();
+        assert!(release.unknown_scopes().is_empty());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod ignore {
+    use super::*;
+    use crate::config::Config;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn drops_ignored_commits() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let config: Config = "ignore:\n  - verb: chore\n  - summary: '^wip'".parse()?;
+        let config = Rc::new(config);
+
+        let msgs = vec!["feat: one", "chore: two", "wip three", "fix: four"];
+        let mut commits = vec![];
+        let mut ids = vec![];
+        for (i, msg) in msgs.into_iter().enumerate() {
+            let (oid, _) = common_test::commit(&repo, &format!("file{i}"), Some(msg));
+            commits.push(Commit::new(repo.find_commit(oid)?, Rc::clone(&config)));
+            ids.push(oid);
+        }
+        let release = Release::new(commits, config);
+        assert_eq!(vec![ids[0], ids[3]], release.commit_ids());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod reverts {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn revert(repo: &git2::Repository, file: &str, oid: git2::Oid) -> git2::Oid {
+        let commit = repo.find_commit(oid).unwrap();
+        let msg = format!(
+            "Revert \"{}\"\n\nThis reverts commit {oid}.",
+            commit.summary().unwrap()
+        );
+        common_test::commit(repo, file, Some(&msg)).0
+    }
+
+    #[test]
+    fn cancels_pairs() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+
+        let (old, _) = common_test::commit(&repo, "file0", Some("feat(repo): from before"));
+        let (feat, _) = common_test::commit(&repo, "file1", Some("feat: one"));
+        let (fix, _) = common_test::commit(&repo, "file2", Some("fix: two"));
+        let revert_feat = revert(&repo, "file3", feat);
+        let revert_old = revert(&repo, "file4", old);
+
+        let ids = [feat, fix, revert_feat, revert_old];
+        let commits = ids
+            .iter()
+            .map(|&oid| repo.find_commit(oid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let release: Release = commits.into();
+
+        assert_eq!(vec![fix, revert_old], release.commit_ids());
+        let want = "### Fix\n\n- Two\n\n### Reverts\n\n- **repo:** From before";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+
+    #[test]
+    fn revert_of_revert() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+
+        let (feat, _) = common_test::commit(&repo, "file1", Some("feat: one"));
+        let revert1 = revert(&repo, "file2", feat);
+        let revert2 = revert(&repo, "file3", revert1);
+
+        let ids = [feat, revert1, revert2];
+        let commits = ids
+            .iter()
+            .map(|&oid| repo.find_commit(oid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let release: Release = commits.into();
+
+        assert_eq!(vec![feat], release.commit_ids());
+        Ok(())
+    }
+}
+
+mod expand_squashed {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn expands_when_configured() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let msg = "Add things (ref #12)\n\n* feat: add a\n* fix: b\n* chore(ci): c";
+        let (squashed, _) = common_test::commit(&repo, "file1", Some(msg));
+        let (fix, _) = common_test::commit(&repo, "file2", Some("fix: other"));
+
+        let release = |config: &str| -> Result<String, Box<dyn std::error::Error>> {
+            let config = Rc::new(config.parse()?);
+            let commits = [squashed, fix]
+                .iter()
+                .map(|&oid| Ok(Commit::new(repo.find_commit(oid)?, Rc::clone(&config))))
+                .collect::<Result<Vec<_>, git2::Error>>()?;
+            let release = Release::new(commits, config);
+            Ok(format!("{release}"))
+        };
+
+        let want = "### Fix\n\n- Other\n\n### Misc\n\n- Add things (ref #12)";
+        assert_eq!(want, release("")?);
+
+        let config = "expand_squashed: true\nignore:\n  - scope: ci";
+        let want = "### Feature\n\n- Add a (ref #12)\n\n### Fix\n\n- B (ref #12)\n- Other";
+        assert_eq!(want, release(config)?);
+
+        let config = "expand_squashed: true\nignore:\n  - summary: '^fix: b$'";
+        let want = "### Feature\n\n- Add a (ref #12)\n\n### Fix\n\n- Other\n\n\
+                    ### Chore\n\n- **ci:** C (ref #12)";
+        assert_eq!(want, release(config)?);
+        Ok(())
+    }
+}
+
+mod remove_duplicates {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn by_patch_id_and_cherry_pick() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let (fix, _) = common_test::commit(&repo, "file1", Some("fix: one"));
+        let (feat, _) = common_test::commit(&repo, "file2", Some("feat: two"));
+        let msg = format!("fix: one again\n\n(cherry picked from commit {fix})");
+        let (picked, _) = common_test::commit(&repo, "file3", Some(&msg));
+        let (same, _) = common_test::commit(&repo, "file4", Some("feat: two"));
+
+        let new_release = |config: &str| -> Result<Release, Box<dyn std::error::Error>> {
+            let config = Rc::new(config.parse()?);
+            let commits = [fix, feat, picked, same]
+                .iter()
+                .map(|&oid| Ok(Commit::new(repo.find_commit(oid)?, Rc::clone(&config))))
+                .collect::<Result<Vec<_>, git2::Error>>()?;
+            let mut release = Release::new(commits, config);
+            let patch_ids = HashMap::from([(feat, feat), (same, feat), (fix, fix)]);
+            release.remove_duplicates(&patch_ids);
+            Ok(release)
+        };
+
+        let release = new_release("")?;
+        assert_eq!(vec![fix, feat], release.commit_ids());
+        let want = "### Feature\n\n- Two\n\n### Fix\n\n- One";
+        assert_eq!(want, format!("{release}"));
+
+        let release = new_release("note_backports: true")?;
+        let want = "### Feature\n\n- Two\n\n### Fix\n\n- One [back-ported]";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+#[cfg(test)]
+#[path = "./remote_test.rs"]
+mod remote_test;
+
+/// Provider is the service that hosts the repository. It decides how the links to the commits,
+/// comparisons and issues are made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    AzureDevOps,
+    /// Gitea, and its fork Forgejo.
+    Gitea,
+    /// A self-hosted service that is not recognised by its host. The links are made the same way
+    /// as github.
+    Other,
+}
+
+impl Provider {
+    /// Returns the provider of the host.
+    fn from_host(host: &str) -> Self {
+        if host == "github.com" || host.starts_with("github.") {
+            Provider::GitHub
+        } else if host.contains("gitlab") {
+            Provider::GitLab
+        } else if host == "bitbucket.org" {
+            Provider::Bitbucket
+        } else if host.ends_with("dev.azure.com") || host.ends_with(".visualstudio.com") {
+            Provider::AzureDevOps
+        } else if ["gitea", "forgejo", "codeberg.org"]
+            .iter()
+            .any(|name| host.contains(name))
+        {
+            Provider::Gitea
+        } else {
+            Provider::Other
+        }
+    }
+
+    /// Returns the name of the provider to be shown to the user.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Provider::GitHub => "github",
+            Provider::GitLab => "gitlab",
+            Provider::Bitbucket => "bitbucket",
+            Provider::AzureDevOps => "azure devops",
+            Provider::Gitea => "gitea",
+            Provider::Other => "unrecognised hosts",
+        }
+    }
+}
+
+/// RemoteInfo holds the information of a remote url of the repository. Both the ssh and the
+/// http(s) urls are supported.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteInfo {
+    /// The scheme of the remote url, e.g. `https` or `ssh`.
+    pub scheme: String,
+    pub host: String,
+    pub port: Option<u16>,
+    /// The user, organisation or namespace of the repository. For gitlab it contains all the
+    /// nested groups, e.g. `group/subgroup`, and for Azure DevOps it is `organisation/project`.
+    pub owner: String,
+    pub repo: String,
+    pub provider: Provider,
+}
+
+impl RemoteInfo {
+    /// Parses the remote url. It returns `None` if the url is not a remote, e.g. a local path.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim().trim_end_matches('/');
+        let url = url.strip_suffix(".git").unwrap_or(url);
+        let (scheme, authority, path) = match url.split_once("://") {
+            Some((scheme, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                (scheme.to_lowercase(), authority, path)
+            },
+            None => {
+                // The scp-like syntax, e.g. git@github.com:user/repo.
+                let (authority, path) = url.split_once(':')?;
+                if authority.contains('/') {
+                    return None;
+                }
+                ("ssh".to_owned(), authority, path)
+            },
+        };
+        let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+        let (host, port) = match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port.parse().ok()?)),
+            None => (authority, None),
+        };
+        if host.is_empty() {
+            return None;
+        }
+        let host = host.to_lowercase();
+        let provider = Provider::from_host(&host);
+
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let (owner, repo) = match provider {
+            Provider::AzureDevOps => azure_devops_path(&host, &segments)?,
+            _ => {
+                let (repo, owner) = segments.split_last()?;
+                if owner.is_empty() {
+                    return None;
+                }
+                (owner.join("/"), repo.to_string())
+            },
+        };
+        Some(RemoteInfo {
+            scheme,
+            host,
+            port,
+            owner,
+            repo,
+            provider,
+        })
+    }
+
+    /// Returns the url of the web server of the host, e.g. `https://gitlab.example.com`. The
+    /// port is only kept for the http(s) remotes.
+    pub fn base_url(&self) -> String {
+        let (scheme, port) = match self.scheme.as_str() {
+            "http" | "https" => (self.scheme.as_str(), self.port),
+            _ => ("https", None),
+        };
+        let host = match self.host.as_str() {
+            "ssh.dev.azure.com" => "dev.azure.com",
+            host => host,
+        };
+        match port {
+            Some(port) => format!("{scheme}://{host}:{port}"),
+            None => format!("{scheme}://{host}"),
+        }
+    }
+
+    /// Returns the url of the repository's web page.
+    pub fn web_url(&self) -> String {
+        match self.provider {
+            Provider::AzureDevOps => format!(
+                "{}/{}/_git/{}",
+                self.azure_base_url(),
+                self.azure_project(),
+                self.repo
+            ),
+            _ => format!("{}/{}/{}", self.base_url(), self.owner, self.repo),
+        }
+    }
+
+    /// Returns the link to the commit with the given id.
+    pub fn commit_url(&self, id: &str) -> String {
+        let web_url = self.web_url();
+        match self.provider {
+            Provider::GitLab => format!("{web_url}/-/commit/{id}"),
+            Provider::Bitbucket => format!("{web_url}/commits/{id}"),
+            _ => format!("{web_url}/commit/{id}"),
+        }
+    }
+
+    /// Returns the link to the changes between the two revisions, e.g. two tags.
+    pub fn compare_url(&self, from: &str, to: &str) -> String {
+        let web_url = self.web_url();
+        match self.provider {
+            Provider::GitLab => format!("{web_url}/-/compare/{from}...{to}"),
+            Provider::Bitbucket => format!("{web_url}/branches/compare/{to}%0D{from}"),
+            Provider::AzureDevOps => {
+                format!("{web_url}/branchCompare?baseVersion=GT{from}&targetVersion=GT{to}")
+            },
+            _ => format!("{web_url}/compare/{from}...{to}"),
+        }
+    }
+
+    /// Returns the link to the issue with the given number. For Azure DevOps this is the work
+    /// item of the project.
+    pub fn issue_url(&self, number: u64) -> String {
+        match self.provider {
+            Provider::GitLab => format!("{}/-/issues/{number}", self.web_url()),
+            Provider::AzureDevOps => format!(
+                "{}/{}/_workitems/edit/{number}",
+                self.azure_base_url(),
+                self.azure_project()
+            ),
+            _ => format!("{}/issues/{number}", self.web_url()),
+        }
+    }
+
+    /// Returns the link to the merge request with the given number on gitlab.
+    pub fn merge_request_url(&self, number: u64) -> String {
+        format!("{}/-/merge_requests/{number}", self.web_url())
+    }
+
+    /// Returns the url of the organisation in Azure DevOps. The old `visualstudio.com` hosts
+    /// contain the organisation in the host.
+    fn azure_base_url(&self) -> String {
+        let base_url = self.base_url();
+        if self.host.ends_with(".visualstudio.com") {
+            return base_url;
+        }
+        let organisation = self.owner.split('/').next().unwrap_or_default();
+        format!("{base_url}/{organisation}")
+    }
+
+    /// Returns the project of the repository in Azure DevOps.
+    fn azure_project(&self) -> &str {
+        self.owner.rsplit('/').next().unwrap_or_default()
+    }
+}
+
+/// Returns the `organisation/project` and the repository of an Azure DevOps remote path. These
+/// are the supported formats:
+///
+/// - `https://dev.azure.com/{organisation}/{project}/_git/{repo}`
+/// - `git@ssh.dev.azure.com:v3/{organisation}/{project}/{repo}`
+/// - `https://{organisation}.visualstudio.com/{project}/_git/{repo}`
+fn azure_devops_path(host: &str, segments: &[&str]) -> Option<(String, String)> {
+    match segments {
+        ["v3", organisation, project, repo] => {
+            Some((format!("{organisation}/{project}"), repo.to_string()))
+        },
+        [organisation, project, "_git", repo] if !host.ends_with(".visualstudio.com") => {
+            Some((format!("{organisation}/{project}"), repo.to_string()))
+        },
+        [.., project, "_git", repo] => {
+            let organisation = host.strip_suffix(".visualstudio.com")?;
+            Some((format!("{organisation}/{project}"), repo.to_string()))
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,226 @@
+use super::*;
+
+fn remote(url: &str) -> RemoteInfo {
+    RemoteInfo::parse(url).unwrap_or_else(|| panic!("could not parse {url}"))
+}
+
+#[cfg(test)]
+mod parse {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn urls() {
+        let tcs = vec![
+            (
+                "git@github.com:arsham/shark.git",
+                "ssh",
+                "github.com",
+                None,
+                "arsham",
+                "shark",
+                Provider::GitHub,
+            ),
+            (
+                "https://github.com/arsham/shark",
+                "https",
+                "github.com",
+                None,
+                "arsham",
+                "shark",
+                Provider::GitHub,
+            ),
+            (
+                "https://github.com/arsham/shark.git\n",
+                "https",
+                "github.com",
+                None,
+                "arsham",
+                "shark",
+                Provider::GitHub,
+            ),
+            (
+                "ssh://git@gitlab.example.com:2222/group/sub/project.git",
+                "ssh",
+                "gitlab.example.com",
+                Some(2222),
+                "group/sub",
+                "project",
+                Provider::GitLab,
+            ),
+            (
+                "https://user@gitlab.com/group/project/",
+                "https",
+                "gitlab.com",
+                None,
+                "group",
+                "project",
+                Provider::GitLab,
+            ),
+            (
+                "git@bitbucket.org:team/repo.git",
+                "ssh",
+                "bitbucket.org",
+                None,
+                "team",
+                "repo",
+                Provider::Bitbucket,
+            ),
+            (
+                "https://org@dev.azure.com/org/project/_git/repo",
+                "https",
+                "dev.azure.com",
+                None,
+                "org/project",
+                "repo",
+                Provider::AzureDevOps,
+            ),
+            (
+                "git@ssh.dev.azure.com:v3/org/project/repo",
+                "ssh",
+                "ssh.dev.azure.com",
+                None,
+                "org/project",
+                "repo",
+                Provider::AzureDevOps,
+            ),
+            (
+                "https://org.visualstudio.com/DefaultCollection/project/_git/repo",
+                "https",
+                "org.visualstudio.com",
+                None,
+                "org/project",
+                "repo",
+                Provider::AzureDevOps,
+            ),
+            (
+                "git@github.example.com:team/repo.git",
+                "ssh",
+                "github.example.com",
+                None,
+                "team",
+                "repo",
+                Provider::GitHub,
+            ),
+            (
+                "http://localhost:3000/arsham/shark.git",
+                "http",
+                "localhost",
+                Some(3000),
+                "arsham",
+                "shark",
+                Provider::Other,
+            ),
+            (
+                "https://codeberg.org/arsham/shark",
+                "https",
+                "codeberg.org",
+                None,
+                "arsham",
+                "shark",
+                Provider::Gitea,
+            ),
+        ];
+        for (url, scheme, host, port, owner, repo, provider) in tcs {
+            let want = RemoteInfo {
+                scheme: scheme.to_owned(),
+                host: host.to_owned(),
+                port,
+                owner: owner.to_owned(),
+                repo: repo.to_owned(),
+                provider,
+            };
+            assert_eq!(Some(want), RemoteInfo::parse(url), "{url}");
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        for url in [
+            "not a url",
+            "/some/path",
+            "../repo",
+            "https://github.com/shark",
+            "git@github.com:",
+        ] {
+            assert_eq!(None, RemoteInfo::parse(url), "{url}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod links {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn web_url() {
+        let tcs = vec![
+            (
+                "git@github.com:arsham/shark.git",
+                "https://github.com/arsham/shark",
+            ),
+            (
+                "ssh://git@gitlab.example.com:2222/group/sub/project.git",
+                "https://gitlab.example.com/group/sub/project",
+            ),
+            (
+                "http://localhost:3000/arsham/shark.git",
+                "http://localhost:3000/arsham/shark",
+            ),
+            (
+                "git@ssh.dev.azure.com:v3/org/project/repo",
+                "https://dev.azure.com/org/project/_git/repo",
+            ),
+            (
+                "https://org.visualstudio.com/project/_git/repo",
+                "https://org.visualstudio.com/project/_git/repo",
+            ),
+        ];
+        for (url, want) in tcs {
+            assert_eq!(want, remote(url).web_url(), "{url}");
+        }
+    }
+
+    #[test]
+    fn commit_compare_and_issue() {
+        let tcs = vec![
+            (
+                "git@github.com:arsham/shark.git",
+                "https://github.com/arsham/shark/commit/abc",
+                "https://github.com/arsham/shark/compare/v1...v2",
+                "https://github.com/arsham/shark/issues/12",
+            ),
+            (
+                "git@gitlab.com:group/sub/project.git",
+                "https://gitlab.com/group/sub/project/-/commit/abc",
+                "https://gitlab.com/group/sub/project/-/compare/v1...v2",
+                "https://gitlab.com/group/sub/project/-/issues/12",
+            ),
+            (
+                "git@bitbucket.org:team/repo.git",
+                "https://bitbucket.org/team/repo/commits/abc",
+                "https://bitbucket.org/team/repo/branches/compare/v2%0Dv1",
+                "https://bitbucket.org/team/repo/issues/12",
+            ),
+            (
+                "https://dev.azure.com/org/project/_git/repo",
+                "https://dev.azure.com/org/project/_git/repo/commit/abc",
+                "https://dev.azure.com/org/project/_git/repo/branchCompare?baseVersion=GTv1&targetVersion=GTv2",
+                "https://dev.azure.com/org/project/_workitems/edit/12",
+            ),
+            (
+                "https://git.example.com/arsham/shark",
+                "https://git.example.com/arsham/shark/commit/abc",
+                "https://git.example.com/arsham/shark/compare/v1...v2",
+                "https://git.example.com/arsham/shark/issues/12",
+            ),
+        ];
+        for (url, commit, compare, issue) in tcs {
+            let remote = remote(url);
+            assert_eq!(commit, remote.commit_url("abc"), "{url}");
+            assert_eq!(compare, remote.compare_url("v1", "v2"), "{url}");
+            assert_eq!(issue, remote.issue_url(12), "{url}");
+        }
+    }
+}
@@ -1,30 +1,37 @@
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use git2::Commit;
-use lazy_static::lazy_static;
-use regex::Regex;
 
+use crate::config::Merges;
 use crate::workspace::errors;
+use crate::workspace::remote::RemoteInfo;
+use crate::workspace::tag::TagInfo;
 
 #[cfg(test)]
 #[path = "./repository_test.rs"]
 mod repository_test;
 
-lazy_static! {
-    static ref REPO_RE: Regex =
-        Regex::new(r#"github.com[:/](?P<user>[^/]+)/(?P<repo>.+?)(?:.git)?\n?$"#).unwrap();
-}
-
 /// Repository represents the workspace in a git repository. You can use this struct to query for
 /// various information against the underlying database.
 pub struct Repository {
     repo: git2::Repository,
+    merges: Merges,
 }
 
 impl Repository {
     pub fn new<T: AsRef<Path>>(dir: T) -> Result<Self, errors::GRError> {
         let repo = git2::Repository::open(dir).map_err(errors::GRError::Repository)?;
-        Ok(Self { repo })
+        Ok(Self {
+            repo,
+            merges: Merges::default(),
+        })
+    }
+
+    /// Sets the policy for the merge commits returned by `commits_between_tags`.
+    pub fn with_merges(mut self, merges: Merges) -> Self {
+        self.merges = merges;
+        self
     }
 
     /// Returns the latest tag in the chronological order.
@@ -37,7 +44,7 @@ impl Repository {
             .repo
             .tag_names(None)
             .map_err(errors::GRError::TagNameList)?;
-        if let Some(Some(tag)) = tags.iter().last() {
+        if let Some(Some(tag)) = tags.iter().next_back() {
             Ok(tag.to_owned())
         } else {
             Err(errors::GRError::TagNotFound("latest".to_owned()))
@@ -85,6 +92,7 @@ impl Repository {
 
     /// Returns and iterator that would produce all commits between two tags. It excludes the
     /// commit that `from` is pointing at, and includes the commit that the `to` is pointing at.
+    /// The merge commits are handled based on the merge policy of the repository.
     ///
     /// # Errors
     ///
@@ -94,7 +102,7 @@ impl Repository {
         &self,
         from: &str,
         to: &str,
-    ) -> Result<impl Iterator<Item = Commit>, errors::GRError> {
+    ) -> Result<impl Iterator<Item = Commit<'_>>, errors::GRError> {
         let from_obj = self.repo.revparse_single(from)?;
         let to_obj = self.repo.revparse_single(to)?;
         let from = from_obj.id();
@@ -110,42 +118,178 @@ impl Repository {
         let range = format!("{from}..{to}");
         res.push_range(&range)?;
         res.set_sorting(git2::Sort::REVERSE)?;
-        let res = res.filter_map(Result::ok).filter_map(|oid| {
-            if let Ok(oid) = self.repo.find_commit(oid) {
-                Some(oid)
-            } else {
-                None
-            }
-        });
+        if matches!(self.merges, Merges::FirstParent | Merges::PullRequests) {
+            res.simplify_first_parent()?;
+        }
+        let skip_merges = self.merges == Merges::Skip;
+        let res = res
+            .filter_map(Result::ok)
+            .filter_map(|oid| self.repo.find_commit(oid).ok())
+            .filter(move |commit| !skip_merges || commit.parent_count() < 2);
         Ok(res)
     }
 
-    fn repo_name_username(&self, remote: &str, index: usize) -> Result<String, errors::GRError> {
-        let url = self.repo.find_remote(remote)?;
-        let url = url.url().ok_or_else(|| {
+    /// Returns the commits of the branch that the merge commit merged, which are the commits of
+    /// its second parent that are not reachable from its first parent. They are returned from the
+    /// oldest to the newest, and it is empty if the commit is not a merge.
+    ///
+    /// # Errors
+    ///
+    /// If the history of the parents can't be walked, an `Err` is returned.
+    pub fn branch_commits(&self, merge: &Commit) -> Result<Vec<Commit<'_>>, errors::GRError> {
+        if merge.parent_count() < 2 {
+            return Ok(vec![]);
+        }
+        let mut walk = self.repo.revwalk()?;
+        walk.push(merge.parent_id(1)?)?;
+        walk.hide(merge.parent_id(0)?)?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        walk.map(|oid| Ok(self.repo.find_commit(oid?)?)).collect()
+    }
+
+    /// Returns the contents of the file at the given path in the tree of the given revision. If
+    /// the file doesn't exist, `None` is returned.
+    ///
+    /// # Errors
+    ///
+    /// If the revision is not in the repository, or the path is not a file, an `Err` is returned.
+    pub fn file_contents(&self, rev: &str, path: &str) -> Result<Option<String>, errors::GRError> {
+        let tree = self.repo.revparse_single(rev)?.peel_to_tree()?;
+        let entry = match tree.get_path(Path::new(path)) {
+            Ok(entry) => entry,
+            Err(err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let blob = entry.to_object(&self.repo)?.peel_to_blob()?;
+        Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+    }
+
+    /// Returns the date and the message of the tag. The date of a lightweight tag is the date of
+    /// its commit.
+    ///
+    /// # Errors
+    ///
+    /// If the tag is not in the repository, an `Err` is returned.
+    pub fn tag_info(&self, tag: &str) -> Result<TagInfo, errors::GRError> {
+        let object = self.repo.revparse_single(tag)?;
+        let (time, message) = match object.as_tag() {
+            Some(annotated) => {
+                let time = match annotated.tagger() {
+                    Some(tagger) => tagger.when(),
+                    None => object.peel_to_commit()?.time(),
+                };
+                let message = annotated.message().unwrap_or_default().to_owned();
+                (time, message)
+            },
+            None => (object.peel_to_commit()?.time(), String::new()),
+        };
+        Ok(TagInfo {
+            name: tag.to_owned(),
+            date: date(time),
+            message,
+        })
+    }
+
+    /// Returns the patch id of the changes of the commit, similar to `git patch-id`. Commits with
+    /// the same changes have the same patch id, even if they are applied on different parents.
+    /// Merge commits and commits without any changes don't have a patch id.
+    ///
+    /// # Errors
+    ///
+    /// If the diff of the commit can't be created, an `Err` is returned.
+    pub fn patch_id(&self, commit: &Commit) -> Result<Option<git2::Oid>, errors::GRError> {
+        if commit.parent_count() > 1 {
+            return Ok(None);
+        }
+        let parent = match commit.parent_count() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+        let diff = self
+            .repo
+            .diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+        if diff.deltas().len() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(diff.patchid(None)?))
+    }
+
+    /// Returns the patch ids of the given commits, keyed by the commit ids. The commits without a
+    /// patch id are left out.
+    ///
+    /// # Errors
+    ///
+    /// If any of the commits is not in the repository, an `Err` is returned.
+    pub fn patch_ids(
+        &self,
+        ids: &[git2::Oid],
+    ) -> Result<HashMap<git2::Oid, git2::Oid>, errors::GRError> {
+        let mut patch_ids = HashMap::with_capacity(ids.len());
+        for &id in ids {
+            if let Some(patch_id) = self.patch_id(&self.repo.find_commit(id)?)? {
+                patch_ids.insert(id, patch_id);
+            }
+        }
+        Ok(patch_ids)
+    }
+
+    /// Returns the patch ids of the commits that are reachable from the `reference`, but not from
+    /// the `from` revision. These are the changes that are released in the reference, e.g. with
+    /// cherry-picks, after the `from` revision.
+    ///
+    /// # Errors
+    ///
+    /// If either revisions is not in the repository, an `Err` is returned.
+    pub fn released_patch_ids(
+        &self,
+        reference: &str,
+        from: &str,
+    ) -> Result<HashSet<git2::Oid>, errors::GRError> {
+        let reference = self.repo.revparse_single(reference)?.peel_to_commit()?;
+        let from = self.repo.revparse_single(from)?.peel_to_commit()?;
+        let mut walk = self.repo.revwalk()?;
+        walk.push(reference.id())?;
+        walk.hide(from.id())?;
+        let mut patch_ids = HashSet::new();
+        for oid in walk {
+            if let Some(patch_id) = self.patch_id(&self.repo.find_commit(oid?)?)? {
+                patch_ids.insert(patch_id);
+            }
+        }
+        Ok(patch_ids)
+    }
+
+    /// Returns the url of the remote.
+    ///
+    /// # Errors
+    ///
+    /// If the remote doesn't exist or its url is not valid UTF-8, an `Err` is returned.
+    pub fn remote_url(&self, remote: &str) -> Result<String, errors::GRError> {
+        let remote = self.repo.find_remote(remote)?;
+        let url = remote.url().ok_or_else(|| {
             errors::GRError::URLError("could not get the url of the repository".to_owned())
         })?;
-        if let Some(caps) = REPO_RE.captures(url) {
-            return Ok(caps
-                .get(index)
-                .ok_or_else(|| {
-                    errors::GRError::URLError(format!(
-                        "could not find the setting from the url: {url}",
-                    ))
-                })?
-                .as_str()
-                .to_owned());
-        }
         Ok(url.to_owned())
     }
 
-    /// Returns the repository name.
-    pub fn repo_name(&self, remote: &str) -> Result<String, errors::GRError> {
-        self.repo_name_username(remote, 2)
+    /// Returns the information of the remote, e.g. its host and the owner of the repository.
+    ///
+    /// # Errors
+    ///
+    /// If the remote doesn't exist, or its url is not a supported remote url, an `Err` is
+    /// returned.
+    pub fn remote_info(&self, remote: &str) -> Result<RemoteInfo, errors::GRError> {
+        let url = self.remote_url(remote)?;
+        RemoteInfo::parse(&url)
+            .ok_or_else(|| errors::GRError::URLError(format!("could not parse the url: {url}")))
     }
+}
 
-    /// Returns the user or organisation of the repository.
-    pub fn username(&self, remote: &str) -> Result<String, errors::GRError> {
-        self.repo_name_username(remote, 1)
-    }
+/// Returns the date of the time in its own timezone, in the `YYYY-MM-DD` format.
+fn date(time: git2::Time) -> String {
+    let offset = chrono::FixedOffset::east_opt(time.offset_minutes() * 60)
+        .unwrap_or_else(|| chrono::FixedOffset::east_opt(0).unwrap());
+    chrono::DateTime::from_timestamp(time.seconds(), 0)
+        .map(|date| date.with_timezone(&offset).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
 }
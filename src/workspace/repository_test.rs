@@ -1,5 +1,10 @@
+use std::rc::Rc;
+
 use super::super::super::common_test;
+use crate::config::Config;
+use crate::workspace::commit::Commit;
 use crate::workspace::errors;
+use crate::workspace::release::Release;
 use crate::workspace::repository::Repository;
 
 #[cfg(test)]
@@ -275,7 +280,7 @@ mod getting_names {
         let (dir, repo) = repo_init();
         repo.remote_set_url("origin", "git@github.com:arsham/shark.git")?;
         let repo = Repository::new(dir)?;
-        let name = repo.repo_name("origin")?;
+        let name = repo.remote_info("origin")?.repo;
         assert_eq!("shark", &name);
 
         Ok(())
@@ -286,9 +291,289 @@ mod getting_names {
         let (dir, repo) = repo_init();
         repo.remote_set_url("origin", "git@github.com:arsham/shark.git")?;
         let repo = Repository::new(dir)?;
-        let name = repo.username("origin")?;
+        let name = repo.remote_info("origin")?.owner;
         assert_eq!("arsham", &name);
 
         Ok(())
     }
+
+    #[test]
+    fn invalid_url() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = repo_init();
+        repo.remote_set_url("origin", "/some/path")?;
+        let repo = Repository::new(dir)?;
+        assert!(repo.remote_info("origin").is_err());
+        assert!(repo.remote_info("not_exists").is_err());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod file_contents {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn at_revision() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let path = ".github/release.yml";
+        common_test::commit_file(&repo, path, "first", "add the file");
+        common_test::commit_tag(&repo, "file1", "tag1");
+        common_test::commit_file(&repo, path, "second", "change the file");
+        common_test::commit_tag(&repo, "file2", "tag2");
+
+        let ws = Repository::new(&dir)?;
+        assert_eq!(Some("first".to_owned()), ws.file_contents("tag1", path)?);
+        assert_eq!(Some("second".to_owned()), ws.file_contents("tag2", path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn not_found() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        common_test::commit_tag(&repo, "file1", "tag1");
+
+        let ws = Repository::new(&dir)?;
+        assert_eq!(None, ws.file_contents("tag1", ".github/release.yml")?);
+        assert!(ws
+            .file_contents("not_exists", ".github/release.yml")
+            .is_err());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod merges {
+    use super::*;
+    use crate::config::Merges;
+    use pretty_assertions::assert_eq;
+
+    struct History {
+        branch1: git2::Oid,
+        branch2: git2::Oid,
+        main1: git2::Oid,
+        merge: git2::Oid,
+        main2: git2::Oid,
+    }
+
+    // tag1 - main1 ------- merge - main2 (tag2)
+    //    \                /
+    //     branch1 - branch2
+    fn history(repo: &git2::Repository) -> History {
+        let (base, _) = common_test::commit_tag(repo, "file1", "tag1");
+        let branch1 = common_test::branch_commit(repo, base, "feat: one");
+        let branch2 = common_test::branch_commit(repo, branch1, "fix: two");
+        let (main1, _) = common_test::commit(repo, "file2", Some("feat: three"));
+        let merge = common_test::merge(
+            repo,
+            branch2,
+            "Merge pull request #42 from arsham/branch\n\nfeat: the branch",
+        );
+        let (main2, _) = common_test::commit(repo, "file3", Some("docs: four"));
+        common_test::tag(repo, main2, "tag2");
+        History {
+            branch1,
+            branch2,
+            main1,
+            merge,
+            main2,
+        }
+    }
+
+    fn commits(ws: &Repository) -> Result<Vec<git2::Oid>, errors::GRError> {
+        Ok(ws
+            .commits_between_tags("tag1", "tag2")?
+            .map(|c| c.id())
+            .collect())
+    }
+
+    #[test]
+    fn include() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = common_test::repo_init();
+        let h = history(&repo);
+
+        let ws = Repository::new(&dir)?.with_merges(Merges::Include);
+        let mut got = commits(&ws)?;
+        got.sort();
+        let mut want = vec![h.branch1, h.branch2, h.main1, h.merge, h.main2];
+        want.sort();
+        assert_eq!(want, got);
+        Ok(())
+    }
+
+    #[test]
+    fn skip() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = common_test::repo_init();
+        let h = history(&repo);
+
+        let ws = Repository::new(&dir)?.with_merges(Merges::Skip);
+        let got = commits(&ws)?;
+        assert!(!got.contains(&h.merge));
+        assert_eq!(4, got.len());
+        assert_eq!(Some(&h.main2), got.last());
+        Ok(())
+    }
+
+    #[test]
+    fn first_parent() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = common_test::repo_init();
+        let h = history(&repo);
+
+        let ws = Repository::new(&dir)?.with_merges(Merges::FirstParent);
+        assert_eq!(vec![h.main1, h.merge, h.main2], commits(&ws)?);
+        Ok(())
+    }
+
+    #[test]
+    fn pull_requests() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = common_test::repo_init();
+        let h = history(&repo);
+        let config: Rc<Config> = Rc::new("merges: pull-requests".parse()?);
+
+        let ws = Repository::new(&dir)?.with_merges(Merges::PullRequests);
+        assert_eq!(vec![h.main1, h.merge, h.main2], commits(&ws)?);
+
+        let commits = ws
+            .commits_between_tags("tag1", "tag2")?
+            .map(|c| Commit::new(c, Rc::clone(&config)))
+            .collect();
+        let release = Release::new(commits, config);
+        let want = "### Feature\n\n- Three\n- The branch (ref #42)\n\n### Documentation\n\n- Four";
+        assert_eq!(want, format!("{release}"));
+        Ok(())
+    }
+
+    #[test]
+    fn branch_commits() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = common_test::repo_init();
+        let h = history(&repo);
+
+        let ws = Repository::new(&dir)?;
+        let ids = |oid| -> Result<Vec<git2::Oid>, Box<dyn std::error::Error>> {
+            let commit = repo.find_commit(oid)?;
+            Ok(ws.branch_commits(&commit)?.iter().map(|c| c.id()).collect())
+        };
+        assert_eq!(vec![h.branch1, h.branch2], ids(h.merge)?);
+        assert!(ids(h.main1)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn plain_merge() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, repo) = common_test::repo_init();
+        let (base, _) = common_test::commit_tag(&repo, "file1", "tag1");
+        let branch1 = common_test::branch_commit(&repo, base, "update the readme");
+        let branch2 = common_test::branch_commit(&repo, branch1, "fix(repo): two");
+        let merge = common_test::merge(&repo, branch2, "Merge branch 'feature'");
+        common_test::tag(&repo, merge, "tag2");
+        let config: Rc<Config> = Rc::new("merges: pull-requests".parse()?);
+
+        let ws = Repository::new(&dir)?.with_merges(Merges::PullRequests);
+        let mut commits = vec![];
+        for commit in ws.commits_between_tags("tag1", "tag2")? {
+            let branch = ws.branch_commits(&commit)?;
+            let mut commit = Commit::new(commit, Rc::clone(&config));
+            commit.set_branch(&branch);
+            commits.push(commit);
+        }
+        let release = Release::new(commits, config);
+        assert_eq!("### Fix\n\n- **repo:** Two", format!("{release}"));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod patch_ids {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn same_changes() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let base = repo.refname_to_id("HEAD")?;
+        let fix = common_test::commit_file(&repo, "file1", "fixed", "fix: something");
+        let feat = common_test::commit_file(&repo, "file2", "new", "feat: something");
+        repo.reset(&repo.find_object(base, None)?, git2::ResetType::Hard, None)?;
+        let picked = common_test::cherry_pick(&repo, fix, "fix: something else");
+        let empty = common_test::branch_commit(&repo, picked, "chore: nothing");
+
+        let ws = Repository::new(&dir)?;
+        let patch_ids = ws.patch_ids(&[fix, feat, picked, empty])?;
+        assert_eq!(3, patch_ids.len());
+        assert_eq!(patch_ids[&fix], patch_ids[&picked]);
+        assert_ne!(patch_ids[&fix], patch_ids[&feat]);
+        assert!(!patch_ids.contains_key(&empty));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod released_patch_ids {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cherry_picked_in_other_branch() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let base = common_test::commit_file(&repo, "file0", "base", "chore: base");
+        common_test::tag(&repo, base, "v1.4.2");
+        let fix = common_test::commit_file(&repo, "file1", "fixed", "fix: one");
+        let feat = common_test::commit_file(&repo, "file2", "new", "feat: two");
+        common_test::tag(&repo, feat, "v1.4.3");
+
+        repo.reset(&repo.find_object(base, None)?, git2::ResetType::Hard, None)?;
+        common_test::commit_file(&repo, "file3", "major", "feat!: major");
+        let picked = common_test::cherry_pick(&repo, fix, "fix: one");
+        common_test::tag(&repo, picked, "v2.0.0");
+
+        let ws = Repository::new(&dir)?;
+        let released = ws.released_patch_ids("v2.0.0", "v1.4.2")?;
+        assert_eq!(2, released.len());
+        let patch_ids = ws.patch_ids(&[fix, feat])?;
+        assert!(released.contains(&patch_ids[&fix]));
+        assert!(!released.contains(&patch_ids[&feat]));
+
+        let commits = ws
+            .commits_between_tags("v1.4.2", "v1.4.3")?
+            .map(Commit::from)
+            .collect();
+        let mut release = Release::new(commits, Rc::default());
+        release.remove_released(&patch_ids, &released);
+        assert_eq!(vec![feat], release.commit_ids());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tag_info {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn annotated_and_lightweight() -> Result<(), Box<dyn std::error::Error>> {
+        let (dir, _) = common_test::repo_init();
+        let repo = git2::Repository::open(&dir)?;
+        let (oid, _) = common_test::commit(&repo, "file1", None);
+        let sig = git2::Signature::new("name", "email", &git2::Time::new(1661900400, 120))?;
+        let obj = repo.find_object(oid, None)?;
+        repo.tag("v1.2.0", &obj, &sig, "Bumblebee\n\nThe body.\n", false)?;
+        common_test::commit_lightweight_tag(&repo, "file2", "v1.3.0");
+
+        let ws = Repository::new(&dir)?;
+        let info = ws.tag_info("v1.2.0")?;
+        assert_eq!("v1.2.0", info.name);
+        assert_eq!("2022-08-31", info.date);
+        assert_eq!("Bumblebee", info.codename());
+
+        let info = ws.tag_info("v1.3.0")?;
+        assert_eq!(10, info.date.len());
+        assert_eq!("", info.message);
+        assert!(ws.tag_info("not_exists").is_err());
+        Ok(())
+    }
 }
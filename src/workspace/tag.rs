@@ -0,0 +1,56 @@
+#[cfg(test)]
+#[path = "./tag_test.rs"]
+mod tag_test;
+
+/// The name of the releases when the project doesn't set a template.
+pub const DEFAULT_NAME_TEMPLATE: &str = "Release {tag}";
+
+/// TagInfo holds the information of a tag that is used in the name of its release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagInfo {
+    pub name: String,
+    /// The date of the tag in the `YYYY-MM-DD` format. For the lightweight tags it is the date of
+    /// the commit.
+    pub date: String,
+    /// The message of the annotated tag. It is empty for the lightweight tags.
+    pub message: String,
+}
+
+impl TagInfo {
+    /// Returns the version in the tag without its prefix, e.g. `1.2.0` for `v1.2.0`. If the tag
+    /// doesn't have any digits, the tag is returned.
+    pub fn version(&self) -> &str {
+        match self.name.find(|c: char| c.is_ascii_digit()) {
+            Some(i) => &self.name[i..],
+            None => &self.name,
+        }
+    }
+
+    /// Returns the first line of the message of the annotated tag, e.g. `Bumblebee` when the tag
+    /// is made with `git tag -a v1.2.0 -m Bumblebee`.
+    pub fn codename(&self) -> &str {
+        self.message
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+    }
+
+    /// Returns the name of the release from the template. These placeholders are replaced:
+    ///
+    /// - `{tag}`: the tag, e.g. `v1.2.0`.
+    /// - `{version}`: the tag without its prefix, e.g. `1.2.0`.
+    /// - `{date}`: the date of the tag, e.g. `2022-08-30`.
+    /// - `{project}`: the name of the project.
+    /// - `{codename}`: the first line of the message of the annotated tag.
+    pub fn release_name(&self, template: &str, project: &str) -> String {
+        template
+            .replace("{tag}", &self.name)
+            .replace("{version}", self.version())
+            .replace("{date}", &self.date)
+            .replace("{project}", project)
+            .replace("{codename}", self.codename())
+            .trim()
+            .to_owned()
+    }
+}
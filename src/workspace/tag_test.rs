@@ -0,0 +1,55 @@
+use super::*;
+
+fn tag(name: &str, message: &str) -> TagInfo {
+    TagInfo {
+        name: name.to_owned(),
+        date: "2022-08-30".to_owned(),
+        message: message.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod version {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn prefixes() {
+        let tcs = vec![
+            ("v1.2.0", "1.2.0"),
+            ("1.2.0", "1.2.0"),
+            ("release-1.2", "1.2"),
+            ("latest", "latest"),
+        ];
+        for (name, want) in tcs {
+            assert_eq!(want, tag(name, "").version(), "{name}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod release_name {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn placeholders() {
+        let tag = tag("v1.2.0", "\nBumblebee\n\nThe body of the tag.\n");
+        let tcs = vec![
+            (DEFAULT_NAME_TEMPLATE, "Release v1.2.0"),
+            ("{project} {version}", "shark 1.2.0"),
+            ("{version} ({date})", "1.2.0 (2022-08-30)"),
+            ("{tag} \"{codename}\"", "v1.2.0 \"Bumblebee\""),
+            ("{unknown} {tag}", "{unknown} v1.2.0"),
+        ];
+        for (template, want) in tcs {
+            assert_eq!(want, tag.release_name(template, "shark"), "{template}");
+        }
+    }
+
+    #[test]
+    fn lightweight_tag() {
+        let tag = tag("v1.2.0", "");
+        assert_eq!("v1.2.0", tag.release_name("{tag} {codename}", "shark"));
+    }
+}